@@ -2,7 +2,9 @@ use clap::{CommandFactory, Parser, Subcommand};
 use clap_complete::{Shell, generate};
 use std::io;
 
-use crate::commands::{ShellCmd, ToggleCmd};
+use crate::commands::{
+    ConfigCmd, HyprCmd, RecordCmd, ScreenshotCmd, ShellCmd, ToggleCmd, WallpaperCmd,
+};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -22,6 +24,16 @@ pub enum Command {
 
     Toggle(ToggleCmd),
 
+    Wallpaper(WallpaperCmd),
+
+    Hypr(HyprCmd),
+
+    Screenshot(ScreenshotCmd),
+
+    Record(RecordCmd),
+
+    Config(ConfigCmd),
+
     Completions {
         #[arg(value_enum)]
         shell: Shell,
@@ -0,0 +1,54 @@
+use clap::{Args, Subcommand};
+use std::error::Error;
+use std::fs;
+
+use super::Runnable;
+use crate::utils::config;
+use crate::utils::paths::Paths;
+
+#[derive(Args, Debug)]
+pub struct ConfigCmd {
+    #[command(subcommand)]
+    pub command: ConfigSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigSubcommand {
+    /// Check cli.json against the config schema
+    Validate {
+        /// Config file to check instead of the user cli.json
+        file: Option<String>,
+    },
+
+    /// Print the JSON Schema for cli.json
+    Schema,
+}
+
+impl Runnable<&Paths> for ConfigCmd {
+    fn run(&self, paths: &Paths) -> Result<(), Box<dyn Error>> {
+        match &self.command {
+            ConfigSubcommand::Validate { file } => {
+                let path = file
+                    .as_ref()
+                    .map(Into::into)
+                    .unwrap_or_else(|| paths.user_config_path.clone());
+
+                let content = fs::read_to_string(&path)
+                    .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+                let (_, issues) = config::check(&path, &content);
+
+                if !issues.is_empty() {
+                    for issue in &issues {
+                        println!("{}", issue);
+                    }
+                    return Err(format!("{} problem(s) found", issues.len()).into());
+                }
+                println!("{}: ok", path.display());
+            }
+
+            ConfigSubcommand::Schema => print!("{}", config::SCHEMA),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use clap::{Args, Subcommand};
+use std::error::Error;
+use std::io::{self, Write};
+
+use super::Runnable;
+use crate::utils::hypr::{self, events};
+use crate::utils::paths::Paths;
+
+#[derive(Args, Debug)]
+pub struct HyprCmd {
+    #[command(subcommand)]
+    pub command: HyprSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HyprSubcommand {
+    /// Stream Hyprland events as they happen
+    Events {
+        /// Print one JSON object per event
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the input devices as JSON
+    Devices,
+    /// Print the layer surfaces on each monitor as JSON
+    Layers,
+}
+
+impl Runnable<&Paths> for HyprCmd {
+    fn run(&self, _paths: &Paths) -> Result<(), Box<dyn Error>> {
+        match &self.command {
+            HyprSubcommand::Events { json } => {
+                let mut stdout = io::stdout().lock();
+                for event in events::events()? {
+                    let event = event?;
+                    let line = if *json {
+                        serde_json::to_string(&event)?
+                    } else {
+                        event.summary()
+                    };
+
+                    // Stop quietly when piped into something like `head`.
+                    if writeln!(stdout, "{}", line)
+                        .and_then(|_| stdout.flush())
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            }
+            HyprSubcommand::Devices => {
+                println!("{}", serde_json::to_string_pretty(&hypr::devices()?)?);
+            }
+            HyprSubcommand::Layers => {
+                println!("{}", serde_json::to_string_pretty(&hypr::layers()?)?);
+            }
+        }
+
+        Ok(())
+    }
+}
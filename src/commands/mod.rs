@@ -1,10 +1,20 @@
 use std::error::Error;
 
+pub mod config;
+pub mod hypr;
+pub mod record;
+pub mod screenshot;
 pub mod shell;
 pub mod toggle;
+pub mod wallpaper;
 
+pub use config::ConfigCmd;
+pub use hypr::HyprCmd;
+pub use record::RecordCmd;
+pub use screenshot::ScreenshotCmd;
 pub use shell::ShellCmd;
 pub use toggle::ToggleCmd;
+pub use wallpaper::WallpaperCmd;
 
 pub trait Runnable<Ctx> {
     fn run(&self, context: Ctx) -> Result<(), Box<dyn Error>>;
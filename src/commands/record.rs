@@ -0,0 +1,354 @@
+use clap::{Args, Subcommand};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::Runnable;
+use super::screenshot::{find_monitor, select_region};
+use crate::utils::config::load_user_config;
+use crate::utils::launcher::command_exists;
+use crate::utils::notify::{Category, Notification, NotifyConfig, close_notification, notify};
+use crate::utils::paths::{Paths, lock_file, timestamped_path};
+
+/// Exit code of `record status` when nothing is being recorded.
+const EXIT_NOT_RECORDING: i32 = 3;
+
+/// How long a freshly started recorder gets to fail before it counts as running.
+const STARTUP_GRACE: Duration = Duration::from_millis(200);
+
+/// How long `stop` waits for the recorder to finish writing the file.
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Args, Debug)]
+pub struct RecordCmd {
+    #[command(subcommand)]
+    pub command: RecordSubcommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RecordSubcommand {
+    /// Start recording the screen
+    Start(StartArgs),
+    /// Stop recording and save the video
+    Stop,
+    /// Stop the running recording, or start one
+    Toggle(StartArgs),
+    /// Show whether a recording is running
+    Status,
+}
+
+#[derive(Args, Debug)]
+pub struct StartArgs {
+    /// Record a region picked with slurp
+    #[arg(long, conflicts_with = "output")]
+    pub region: bool,
+
+    /// Monitor to record (defaults to the focused one)
+    #[arg(long, value_name = "NAME")]
+    pub output: Option<String>,
+
+    /// Record the default audio output too
+    #[arg(long)]
+    pub audio: bool,
+}
+
+/// The screen recorders ferret can drive, in order of preference.
+#[derive(Debug, Clone, Copy)]
+enum Recorder {
+    WlScreenrec,
+    WfRecorder,
+}
+
+impl Recorder {
+    fn find() -> Result<Self, Box<dyn Error>> {
+        if command_exists("wl-screenrec") {
+            Ok(Recorder::WlScreenrec)
+        } else if command_exists("wf-recorder") {
+            Ok(Recorder::WfRecorder)
+        } else {
+            Err("neither wl-screenrec nor wf-recorder is installed".into())
+        }
+    }
+
+    fn command(
+        self,
+        file: &Path,
+        geometry: Option<&str>,
+        output: Option<&str>,
+        audio: bool,
+    ) -> Command {
+        let mut cmd = match self {
+            Recorder::WlScreenrec => Command::new("wl-screenrec"),
+            Recorder::WfRecorder => {
+                let mut cmd = Command::new("wf-recorder");
+                // Overwrite without asking on stdin, which is closed.
+                cmd.arg("-y");
+                cmd
+            }
+        };
+        cmd.arg("-f").arg(file);
+        if let Some(geometry) = geometry {
+            cmd.args(["-g", geometry]);
+        }
+        if let Some(output) = output {
+            cmd.args(["-o", output]);
+        }
+        if audio {
+            cmd.arg(match self {
+                Recorder::WlScreenrec => "--audio",
+                Recorder::WfRecorder => "-a",
+            });
+        }
+        cmd
+    }
+}
+
+/// Whether `args` is a recorder writing to `file`.
+fn writes_to(args: &[&str], file: &str) -> bool {
+    args.windows(2)
+        .any(|pair| pair[0] == "-f" && pair[1] == file)
+}
+
+/// The recorder writing to `recording_path`, if one is running.
+fn recorder_pid(paths: &Paths) -> Option<u32> {
+    let file = paths.recording_path.to_str()?;
+    fs::read_dir("/proc").ok()?.flatten().find_map(|entry| {
+        let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+        let raw = fs::read(entry.path().join("cmdline")).ok()?;
+        let cmdline = String::from_utf8_lossy(&raw);
+        let args: Vec<&str> = cmdline.split('\0').filter(|a| !a.is_empty()).collect();
+        writes_to(&args, file).then_some(pid)
+    })
+}
+
+/// Whether `pid` is still running. Zombies count as gone: the recorder is detached, so
+/// nothing may be around to reap it.
+fn is_alive(pid: u32) -> bool {
+    fs::read_to_string(format!("/proc/{}/stat", pid))
+        .ok()
+        .and_then(|stat| {
+            let (_, rest) = stat.rsplit_once(')')?;
+            rest.split_whitespace().next().map(|state| state != "Z")
+        })
+        .unwrap_or(false)
+}
+
+/// Sends SIGINT to `pid`.
+fn interrupt(pid: u32) -> io::Result<()> {
+    let pid = libc::pid_t::try_from(pid).map_err(io::Error::other)?;
+    // SAFETY: kill only takes plain integers.
+    if unsafe { libc::kill(pid, libc::SIGINT) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// The last thing the recorder wrote to stderr, if anything.
+fn recorder_error(paths: &Paths) -> Option<String> {
+    let log = fs::read_to_string(&paths.recorder_log_path).ok()?;
+    log.lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// Moves `from` to `to`, copying when they are on different filesystems.
+fn move_file(from: &Path, to: &Path) -> Result<(), Box<dyn Error>> {
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+/// Moves the finished recording into `recordings_dir` under a timestamped name.
+fn save_recording(paths: &Paths) -> Result<PathBuf, Box<dyn Error>> {
+    fs::create_dir_all(&paths.recordings_dir)?;
+    let file = timestamped_path(&paths.recordings_dir, "recording", "mp4");
+    move_file(&paths.recording_path, &file)?;
+    Ok(file)
+}
+
+/// Closes the "recording" notification saved by `start`, if there is one.
+fn close_recording_notification(paths: &Paths) {
+    let Ok(content) = fs::read_to_string(&paths.recording_notif_path) else {
+        return;
+    };
+    if let Ok(id) = content.trim().parse() {
+        // Best effort: the server may have dropped it already.
+        let _ = close_notification(id);
+    }
+    let _ = fs::remove_file(&paths.recording_notif_path);
+}
+
+fn start(args: &StartArgs, paths: &Paths) -> Result<(), Box<dyn Error>> {
+    if let Some(pid) = recorder_pid(paths) {
+        return Err(format!("already recording (pid {})", pid).into());
+    }
+    let recorder = Recorder::find()?;
+
+    let (geometry, output) = if args.region {
+        let Some(geometry) = select_region()? else {
+            return Ok(());
+        };
+        (Some(geometry), None)
+    } else {
+        (None, Some(find_monitor(args.output.as_deref())?))
+    };
+    let mut what = match &geometry {
+        Some(geometry) => format!("Recording region {}", geometry),
+        None => format!("Recording {}", output.as_deref().unwrap_or_default()),
+    };
+    if args.audio {
+        what.push_str(" with audio");
+    }
+
+    // A recording left behind by a recorder that died is still worth keeping.
+    if paths.recording_path.exists() {
+        close_recording_notification(paths);
+        let file = save_recording(paths)?;
+        eprintln!(
+            "ferret: warning: saved an unfinished recording to {}",
+            file.display()
+        );
+    }
+    if let Some(dir) = paths.recording_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Kept for the whole recording, so there is something to look at if it goes wrong.
+    let log = File::create(&paths.recorder_log_path)?;
+
+    let mut child = recorder
+        .command(
+            &paths.recording_path,
+            geometry.as_deref(),
+            output.as_deref(),
+            args.audio,
+        )
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(log)
+        .process_group(0)
+        .spawn()?;
+
+    thread::sleep(STARTUP_GRACE);
+    if let Some(status) = child.try_wait()? {
+        return Err(match recorder_error(paths) {
+            Some(error) => format!("recorder exited straight away ({}): {}", status, error),
+            None => format!("recorder exited straight away ({})", status),
+        }
+        .into());
+    }
+
+    let user = load_user_config(paths);
+    let notification = Notification::new("Recording started")
+        .category(Category::Recording)
+        .body(what)
+        .icon("media-record")
+        .persistent();
+    // Best effort: recording works without a notification server.
+    if let Ok(Some(id)) = notify(
+        &notification,
+        &NotifyConfig::from_user(user.as_ref(), paths),
+    ) {
+        fs::write(&paths.recording_notif_path, id.to_string())?;
+    }
+
+    Ok(())
+}
+
+fn stop(paths: &Paths) -> Result<(), Box<dyn Error>> {
+    match recorder_pid(paths) {
+        Some(pid) => {
+            // Both recorders finish the file on SIGINT, like Ctrl-C in a terminal.
+            interrupt(pid)
+                .map_err(|e| format!("could not stop the recorder (pid {}): {}", pid, e))?;
+
+            let deadline = Instant::now() + STOP_TIMEOUT;
+            while is_alive(pid) {
+                if Instant::now() >= deadline {
+                    return Err(format!("recorder (pid {}) did not stop", pid).into());
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+        None if paths.recording_path.exists() => {
+            eprintln!("ferret: warning: recorder is gone, saving what it recorded");
+        }
+        None => return Err("not recording".into()),
+    }
+
+    close_recording_notification(paths);
+    let file = save_recording(paths)?;
+    println!("{}", file.display());
+
+    let user = load_user_config(paths);
+    let _ = notify(
+        &Notification::new("Recording saved")
+            .category(Category::Recording)
+            .body(file.display().to_string())
+            .icon("video-x-generic"),
+        &NotifyConfig::from_user(user.as_ref(), paths),
+    );
+    Ok(())
+}
+
+fn status(paths: &Paths) {
+    match recorder_pid(paths) {
+        Some(pid) => println!("recording (pid {})", pid),
+        None => {
+            println!("not recording");
+            process::exit(EXIT_NOT_RECORDING);
+        }
+    }
+}
+
+impl Runnable<&Paths> for RecordCmd {
+    fn run(&self, paths: &Paths) -> Result<(), Box<dyn Error>> {
+        // Held from checking for a recorder until one is started or stopped, so two quick
+        // toggles can't both start one.
+        let _lock = match self.command {
+            RecordSubcommand::Status => None,
+            _ => Some(lock_file(&paths.recording_lock_path)?),
+        };
+
+        match &self.command {
+            RecordSubcommand::Start(args) => start(args, paths),
+            RecordSubcommand::Stop => stop(paths),
+            RecordSubcommand::Toggle(args) => {
+                if recorder_pid(paths).is_some() {
+                    stop(paths)
+                } else {
+                    start(args, paths)
+                }
+            }
+            RecordSubcommand::Status => {
+                status(paths);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writes_to() {
+        let file = "/state/record/recording.mp4";
+
+        assert!(writes_to(&["wl-screenrec", "-f", file, "-o", "DP-1"], file));
+        assert!(!writes_to(&["mpv", file], file));
+        assert!(!writes_to(&["wf-recorder", "-f", "/tmp/other.mp4"], file));
+    }
+}
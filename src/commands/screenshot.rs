@@ -0,0 +1,299 @@
+use clap::{Args, ValueEnum};
+use serde::Deserialize;
+use serde_json::Value;
+use std::env;
+use std::error::Error;
+use std::fs::{self, File};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use super::Runnable;
+use crate::utils::config::{ConfigIssue, load_user_config};
+use crate::utils::hypr;
+use crate::utils::launcher::command_exists;
+use crate::utils::notify::{Category, Notification, Notifier, NotifyConfig};
+use crate::utils::paths::{Paths, timestamped_path};
+
+/// How long the screenshot notification keeps its buttons before it is taken down.
+const ACTION_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// A region picked with slurp
+    Region,
+    /// The focused window
+    Window,
+    /// One monitor
+    Monitor,
+    /// Every monitor
+    Full,
+}
+
+#[derive(Args, Debug)]
+pub struct ScreenshotCmd {
+    /// What to capture
+    #[arg(value_enum, default_value_t = Mode::Region)]
+    pub mode: Mode,
+
+    /// Monitor to capture in monitor mode (defaults to the focused one)
+    #[arg(long, value_name = "NAME")]
+    pub output: Option<String>,
+
+    /// Keep the screenshot in the cache instead of the screenshots directory
+    #[arg(long)]
+    pub no_save: bool,
+
+    /// Don't copy the screenshot to the clipboard
+    #[arg(long)]
+    pub no_copy: bool,
+
+    /// Don't show a notification
+    #[arg(long)]
+    pub no_notify: bool,
+
+    /// Show the notification for a taken screenshot and act on its buttons
+    #[arg(long, hide = true, value_name = "FILE")]
+    pub actions_for: Option<PathBuf>,
+}
+
+/// The `screenshot` section of `cli.json`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ScreenshotConfig {
+    /// Command run by the notification's edit button; `{file}` is replaced with the
+    /// screenshot, which is otherwise appended.
+    pub editor: String,
+}
+
+impl Default for ScreenshotConfig {
+    fn default() -> Self {
+        ScreenshotConfig {
+            editor: "swappy -f {file}".to_string(),
+        }
+    }
+}
+
+impl ScreenshotConfig {
+    pub fn from_user(user: Option<&Value>, paths: &Paths) -> Self {
+        let Some(section) = user.and_then(|v| v.get("screenshot")) else {
+            return ScreenshotConfig::default();
+        };
+        serde_json::from_value(section.clone()).unwrap_or_else(|e| {
+            ConfigIssue::new(&paths.user_config_path, "/screenshot", e.to_string()).warn();
+            ScreenshotConfig::default()
+        })
+    }
+}
+
+/// What grim is asked to capture.
+enum Target {
+    Geometry(String),
+    Output(String),
+    Everything,
+}
+
+impl Target {
+    fn grim_args(&self) -> Vec<&str> {
+        match self {
+            Target::Geometry(geometry) => vec!["-g", geometry],
+            Target::Output(name) => vec!["-o", name],
+            Target::Everything => Vec::new(),
+        }
+    }
+}
+
+/// A region picked with slurp as `X,Y WxH`, or `None` when the selection is cancelled.
+pub fn select_region() -> Result<Option<String>, Box<dyn Error>> {
+    if !command_exists("slurp") {
+        return Err("slurp is not installed; it is needed to select a region".into());
+    }
+    let output = Command::new("slurp").output()?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8(output.stdout)?.trim().to_string()))
+}
+
+/// The monitor called `name`, or the focused one.
+pub fn find_monitor(name: Option<&str>) -> Result<String, Box<dyn Error>> {
+    let monitors = hypr::monitors()?;
+    let monitor = match name {
+        Some(name) => monitors
+            .into_iter()
+            .find(|m| m.name == name)
+            .ok_or_else(|| format!("no monitor named {}", name))?,
+        None => monitors
+            .into_iter()
+            .find(|m| m.focused)
+            .ok_or("no monitor is focused")?,
+    };
+    Ok(monitor.name)
+}
+
+/// `command` split into words, with `{file}` replaced by `file` or `file` appended.
+fn command_with_file(command: &str, file: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    let file = file.to_string_lossy();
+    let mut args = shell_words::split(command)?;
+    if args.iter().any(|arg| arg.contains("{file}")) {
+        for arg in &mut args {
+            *arg = arg.replace("{file}", &file);
+        }
+    } else {
+        args.push(file.into_owned());
+    }
+    Ok(args)
+}
+
+/// Starts `args` without waiting for it.
+fn spawn_detached(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let (program, rest) = args.split_first().ok_or("empty command")?;
+    Command::new(program)
+        .args(rest)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()?;
+    Ok(())
+}
+
+impl ScreenshotCmd {
+    /// The area to capture, or `None` when region selection is cancelled.
+    fn target(&self) -> Result<Option<Target>, Box<dyn Error>> {
+        match self.mode {
+            Mode::Region => Ok(select_region()?.map(Target::Geometry)),
+
+            Mode::Window => {
+                let window = hypr::active_window()?.ok_or("no window is focused")?;
+                Ok(Some(Target::Geometry(format!(
+                    "{},{} {}x{}",
+                    window.at[0], window.at[1], window.size[0], window.size[1]
+                ))))
+            }
+
+            Mode::Monitor => Ok(Some(Target::Output(find_monitor(self.output.as_deref())?))),
+
+            Mode::Full => Ok(Some(Target::Everything)),
+        }
+    }
+
+    fn copy_to_clipboard(&self, file: &Path) -> Result<(), Box<dyn Error>> {
+        if !command_exists("wl-copy") {
+            return Err("wl-copy not found".into());
+        }
+        let status = Command::new("wl-copy")
+            .args(["--type", "image/png"])
+            .stdin(File::open(file)?)
+            .status()?;
+        if !status.success() {
+            return Err(format!("wl-copy failed ({})", status).into());
+        }
+        Ok(())
+    }
+
+    fn take(&self, paths: &Paths) -> Result<(), Box<dyn Error>> {
+        if !command_exists("grim") {
+            return Err("grim is not installed; it is needed for screenshots".into());
+        }
+        let Some(target) = self.target()? else {
+            return Ok(());
+        };
+
+        let dir = if self.no_save {
+            &paths.screenshots_cache_dir
+        } else {
+            &paths.screenshots_dir
+        };
+        fs::create_dir_all(dir)?;
+        let file = timestamped_path(dir, "screenshot", "png");
+
+        let status = Command::new("grim")
+            .args(target.grim_args())
+            .arg(&file)
+            .status()?;
+        if !status.success() {
+            return Err(format!("grim failed ({})", status).into());
+        }
+
+        if !self.no_copy
+            && let Err(e) = self.copy_to_clipboard(&file)
+        {
+            eprintln!("ferret: warning: not copying the screenshot: {}", e);
+        }
+        println!("{}", file.display());
+
+        if !self.no_notify {
+            // The notification waits for a button, so it is handled by a detached ferret.
+            Command::new(env::current_exe()?)
+                .arg("screenshot")
+                .arg("--actions-for")
+                .arg(&file)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .process_group(0)
+                .spawn()?;
+        }
+        Ok(())
+    }
+}
+
+/// Shows the notification for `file` and runs whichever button is pressed.
+fn handle_actions(file: &Path, paths: &Paths) -> Result<(), Box<dyn Error>> {
+    let user = load_user_config(paths);
+    let config = ScreenshotConfig::from_user(user.as_ref(), paths);
+    let notifier = Notifier::connect(&NotifyConfig::from_user(user.as_ref(), paths))?;
+
+    let notification = Notification::new("Screenshot taken")
+        .category(Category::Screenshots)
+        .body(file.display().to_string())
+        .icon(format!("file://{}", file.display()))
+        .timeout(ACTION_TIMEOUT)
+        // The default action is a click on the notification itself, so it has no label.
+        .action("default", "")
+        .action("open", "Open")
+        .action("edit", "Edit")
+        .action("delete", "Delete");
+
+    let mut picked = None;
+    notifier.send_and_wait(&notification, ACTION_TIMEOUT, |key| {
+        picked = Some(key.to_string())
+    })?;
+
+    match picked.as_deref() {
+        Some("default" | "open") => spawn_detached(&command_with_file("xdg-open", file)?),
+        Some("edit") => spawn_detached(&command_with_file(&config.editor, file)?),
+        Some("delete") => Ok(fs::remove_file(file)?),
+        _ => Ok(()),
+    }
+}
+
+impl Runnable<&Paths> for ScreenshotCmd {
+    fn run(&self, paths: &Paths) -> Result<(), Box<dyn Error>> {
+        match &self.actions_for {
+            Some(file) => handle_actions(file, paths),
+            None => self.take(paths),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_with_file() {
+        let file = Path::new("/shots/a b.png");
+
+        assert_eq!(
+            command_with_file("swappy -f {file}", file).unwrap(),
+            vec!["swappy", "-f", "/shots/a b.png"]
+        );
+        assert_eq!(
+            command_with_file("satty --filename", file).unwrap(),
+            vec!["satty", "--filename", "/shots/a b.png"]
+        );
+    }
+}
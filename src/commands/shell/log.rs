@@ -0,0 +1,449 @@
+//! Filtering and formatting of the shell's log output, shared by `ferret shell`,
+//! `ferret shell --supervise` and `ferret shell --log`.
+
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::env;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
+use std::process::Child;
+use std::sync::{LazyLock, Mutex};
+use std::thread;
+
+use super::logfile::LogFile;
+use crate::utils::config::ConfigIssue;
+use crate::utils::paths::Paths;
+
+/// Quickshell complains about every wallpaper thumbnail not generated yet.
+const IMAGECACHE_EXCLUDE: &str = r"Cannot open: file://.*/imagecache/";
+
+static ANSI_ESCAPE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\x1b\[[0-9;]*m").unwrap());
+
+/// A Quickshell log line: `[date] [time] LEVEL [category]: message`.
+static LOG_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\s*(?:(?:(\d{4}-\d{2}-\d{2})[ T])?(\d{2}:\d{2}:\d{2}(?:\.\d+)?)\s+)?(DEBUG|INFO|WARN|ERROR|FATAL)(?:\s+([\w.-]+))?:\s?(.*)$",
+    )
+    .unwrap()
+});
+
+/// The `shell.log` section of `cli.json`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LogConfig {
+    /// Only lines matching one of these are shown, when any are set.
+    pub include: Vec<String>,
+    /// Lines matching any of these are hidden.
+    pub exclude: Vec<String>,
+    /// Size in bytes at which `shell.log` is rotated.
+    pub max_file_size: u64,
+    /// Log files kept, counting the current one. 0 disables writing them.
+    pub max_files: usize,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            include: Vec::new(),
+            exclude: vec![IMAGECACHE_EXCLUDE.to_string()],
+            max_file_size: 1024 * 1024,
+            max_files: 5,
+        }
+    }
+}
+
+impl LogConfig {
+    pub fn from_user(user: Option<&Value>, paths: &Paths) -> Self {
+        let Some(section) = user.and_then(|v| v.pointer("/shell/log")) else {
+            return LogConfig::default();
+        };
+        serde_json::from_value(section.clone()).unwrap_or_else(|e| {
+            ConfigIssue::new(&paths.user_config_path, "/shell/log", e.to_string()).warn();
+            LogConfig::default()
+        })
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl Level {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "DEBUG" => Some(Level::Debug),
+            "INFO" => Some(Level::Info),
+            "WARN" => Some(Level::Warn),
+            "ERROR" => Some(Level::Error),
+            "FATAL" => Some(Level::Fatal),
+            _ => None,
+        }
+    }
+
+    /// ANSI colour the line is printed in, if any.
+    fn colour(self) -> Option<&'static str> {
+        match self {
+            Level::Debug => Some("34"),
+            Level::Info => None,
+            Level::Warn => Some("33"),
+            Level::Error | Level::Fatal => Some("31"),
+        }
+    }
+}
+
+/// One line of shell output, split into its parts when it is a Quickshell log message.
+/// Continuation lines (stack traces, multi-line messages) only have `message` set.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LogLine {
+    pub date: Option<String>,
+    pub time: Option<String>,
+    pub level: Option<Level>,
+    pub category: Option<String>,
+    pub message: String,
+    #[serde(skip)]
+    pub text: String,
+}
+
+impl LogLine {
+    pub fn parse(raw: &str) -> Self {
+        let text = ANSI_ESCAPE.replace_all(raw, "").into_owned();
+        let Some(caps) = LOG_LINE.captures(&text) else {
+            return LogLine {
+                date: None,
+                time: None,
+                level: None,
+                category: None,
+                message: text.clone(),
+                text,
+            };
+        };
+
+        let group = |i| caps.get(i).map(|m| m.as_str().to_string());
+        LogLine {
+            date: group(1),
+            time: group(2),
+            level: Level::parse(&caps[3]),
+            category: group(4),
+            message: caps[5].to_string(),
+            text: text.clone(),
+        }
+    }
+
+    /// When the line was logged, if it carries a timestamp. Lines with only a time of day
+    /// are taken to be from `today`.
+    fn timestamp(&self, today: NaiveDate) -> Option<NaiveDateTime> {
+        let time = NaiveTime::parse_from_str(self.time.as_deref()?, "%H:%M:%S%.f").ok()?;
+        let date = match &self.date {
+            Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?,
+            None => today,
+        };
+        Some(date.and_time(time))
+    }
+}
+
+#[derive(Debug)]
+pub enum SinceError {
+    Invalid(String),
+    /// A duration too long to go back from now.
+    OutOfRange(String),
+}
+
+impl fmt::Display for SinceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SinceError::Invalid(s) => write!(
+                f,
+                "invalid time {:?}: expected a duration like 10m or a time like 14:30 or 2024-05-01 14:30",
+                s
+            ),
+            SinceError::OutOfRange(s) => write!(f, "{:?} goes back too far", s),
+        }
+    }
+}
+
+impl std::error::Error for SinceError {}
+
+/// Parses `--since` relative to `now`: a duration back (`30s`, `10m`, `2h`, `1d`), a time
+/// today (`14:30`, `14:30:15`) or a full date and time.
+fn since_from(s: &str, now: NaiveDateTime) -> Result<NaiveDateTime, SinceError> {
+    let err = || SinceError::Invalid(s.to_string());
+    let s = s.trim();
+
+    if let Some(unit) = s.chars().last().filter(char::is_ascii_alphabetic) {
+        let amount: i64 = s[..s.len() - 1].parse().map_err(|_| err())?;
+        let duration = match unit {
+            's' => TimeDelta::try_seconds(amount),
+            'm' => TimeDelta::try_minutes(amount),
+            'h' => TimeDelta::try_hours(amount),
+            'd' => TimeDelta::try_days(amount),
+            _ => return Err(err()),
+        };
+        return duration
+            .and_then(|d| now.checked_sub_signed(d))
+            .ok_or_else(|| SinceError::OutOfRange(s.to_string()));
+    }
+
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(s, format) {
+            return Ok(datetime);
+        }
+    }
+    for format in ["%H:%M:%S", "%H:%M"] {
+        if let Ok(time) = NaiveTime::parse_from_str(s, format) {
+            return Ok(now.date().and_time(time));
+        }
+    }
+    Err(err())
+}
+
+/// Value parser for `--since`.
+pub fn parse_since(s: &str) -> Result<NaiveDateTime, SinceError> {
+    since_from(s, Local::now().naive_local())
+}
+
+/// Whether output goes to a terminal that wants colours.
+pub fn colour_enabled() -> bool {
+    io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none()
+}
+
+/// Filters shell output lines and prints them as text or JSON, keeping every line in the
+/// log files if one is attached.
+pub struct LogPrinter {
+    file: Option<LogFile>,
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+    json: bool,
+    colour: bool,
+    since: Option<NaiveDateTime>,
+    today: NaiveDate,
+    /// Level of the last message, applied to its continuation lines.
+    level: Option<Level>,
+    /// Whether the last timestamped line was recent enough for `since`.
+    recent: bool,
+}
+
+impl LogPrinter {
+    pub fn new(config: &LogConfig, paths: &Paths) -> Self {
+        let compile = |key: &str, patterns: &[String]| -> Vec<Regex> {
+            patterns
+                .iter()
+                .enumerate()
+                .filter_map(|(i, pattern)| {
+                    Regex::new(pattern)
+                        .map_err(|e| {
+                            let pointer = format!("/shell/log/{}/{}", key, i);
+                            ConfigIssue::new(&paths.user_config_path, pointer, e.to_string()).warn()
+                        })
+                        .ok()
+                })
+                .collect()
+        };
+
+        LogPrinter {
+            file: None,
+            include: compile("include", &config.include),
+            exclude: compile("exclude", &config.exclude),
+            json: false,
+            colour: false,
+            since: None,
+            today: Local::now().date_naive(),
+            level: None,
+            recent: true,
+        }
+    }
+
+    pub fn file(mut self, file: Option<LogFile>) -> Self {
+        self.file = file;
+        self
+    }
+
+    pub fn json(mut self, json: bool) -> Self {
+        self.json = json;
+        self
+    }
+
+    pub fn colour(mut self, colour: bool) -> Self {
+        self.colour = colour;
+        self
+    }
+
+    /// Hides lines logged before `since`, along with untimestamped lines until the first
+    /// timestamp shows they are recent enough.
+    pub fn since(mut self, since: Option<NaiveDateTime>) -> Self {
+        self.since = since;
+        self.recent = since.is_none();
+        self
+    }
+
+    fn allows(&self, text: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|re| re.is_match(text)))
+            && !self.exclude.iter().any(|re| re.is_match(text))
+    }
+
+    /// Parses `raw` and returns it formatted for output, or `None` if it is filtered out.
+    pub fn format(&mut self, raw: &str) -> Option<String> {
+        let mut line = LogLine::parse(raw);
+
+        match line.level {
+            Some(level) => self.level = Some(level),
+            None => line.level = self.level,
+        }
+        if let (Some(since), Some(timestamp)) = (self.since, line.timestamp(self.today)) {
+            self.recent = timestamp >= since;
+        }
+        if !self.recent || !self.allows(&line.text) {
+            return None;
+        }
+
+        if self.json {
+            return serde_json::to_string(&line).ok();
+        }
+        match line.level.and_then(Level::colour).filter(|_| self.colour) {
+            Some(colour) => Some(format!("\x1b[{}m{}\x1b[0m", colour, line.text)),
+            None => Some(line.text),
+        }
+    }
+
+    /// Prints a line of shell stdout, or shell output read back from the logs.
+    pub fn print(&mut self, raw: &str) -> io::Result<()> {
+        self.write(raw, &mut io::stdout().lock())
+    }
+
+    /// Prints a line of shell stderr, keeping it on stderr.
+    pub fn eprint(&mut self, raw: &str) -> io::Result<()> {
+        self.write(raw, &mut io::stderr().lock())
+    }
+
+    fn write(&mut self, raw: &str, out: &mut impl Write) -> io::Result<()> {
+        if let Some(file) = &mut self.file
+            && let Err(e) = file.write_line(&ANSI_ESCAPE.replace_all(raw, ""))
+        {
+            eprintln!("ferret: warning: could not write shell log: {}", e);
+            self.file = None;
+        }
+
+        match self.format(raw) {
+            Some(line) => writeln!(out, "{}", line),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Prints the piped stdout and stderr of `child` through `printer`, each to our own, until
+/// both close. Each stderr line is also handed to `on_stderr`.
+pub fn forward(
+    child: &mut Child,
+    printer: &Mutex<LogPrinter>,
+    mut on_stderr: impl FnMut(&str) + Send,
+) -> io::Result<()> {
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    thread::scope(|scope| {
+        if let Some(stderr) = stderr {
+            scope.spawn(move || {
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    // Keep reading even if our own output is gone, or the shell blocks.
+                    let _ = printer.lock().unwrap().eprint(&line);
+                    on_stderr(&line);
+                }
+            });
+        }
+        if let Some(stdout) = stdout {
+            for line in BufReader::new(stdout).lines() {
+                let _ = printer.lock().unwrap().print(&line?);
+            }
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn printer(config: LogConfig) -> LogPrinter {
+        LogPrinter::new(&config, &Paths::new())
+    }
+
+    #[test]
+    fn test_parse_quickshell_lines() {
+        let line = LogLine::parse("\x1b[33m  WARN scene\x1b[0m: Binding loop detected");
+        assert_eq!(line.level, Some(Level::Warn));
+        assert_eq!(line.category.as_deref(), Some("scene"));
+        assert_eq!(line.message, "Binding loop detected");
+        assert_eq!(line.text, "  WARN scene: Binding loop detected");
+
+        let line = LogLine::parse("2024-05-01 14:30:12.345  INFO: Configuration Loaded");
+        assert_eq!(line.date.as_deref(), Some("2024-05-01"));
+        assert_eq!(line.time.as_deref(), Some("14:30:12.345"));
+        assert_eq!(line.level, Some(Level::Info));
+        assert_eq!(line.category, None);
+
+        let line = LogLine::parse("    at onClicked (Bar.qml:12)");
+        assert_eq!(line.level, None);
+        assert_eq!(line.message, "    at onClicked (Bar.qml:12)");
+    }
+
+    #[test]
+    fn test_filters_and_continuation_levels() {
+        let mut printer = printer(LogConfig {
+            include: vec!["qml|at ".to_string()],
+            ..Default::default()
+        })
+        .colour(true);
+
+        assert_eq!(printer.format("  INFO: Configuration Loaded"), None);
+        assert_eq!(
+            printer.format(" ERROR qml: TypeError").as_deref(),
+            Some("\x1b[31m ERROR qml: TypeError\x1b[0m")
+        );
+        assert_eq!(
+            printer.format("    at Bar.qml:12").as_deref(),
+            Some("\x1b[31m    at Bar.qml:12\x1b[0m")
+        );
+        assert_eq!(
+            printer
+                .format("  WARN qml: Cannot open: file:///home/u/.cache/ferret/imagecache/a.png"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_since() {
+        let now =
+            NaiveDateTime::parse_from_str("2024-05-01 14:30:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let at = |s| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+
+        assert_eq!(since_from("10m", now).unwrap(), at("2024-05-01 14:20:00"));
+        assert_eq!(since_from("1d", now).unwrap(), at("2024-04-30 14:30:00"));
+        assert_eq!(since_from("09:15", now).unwrap(), at("2024-05-01 09:15:00"));
+        assert_eq!(
+            since_from("2024-04-01 08:00", now).unwrap(),
+            at("2024-04-01 08:00:00")
+        );
+        assert!(since_from("yesterday", now).is_err());
+        assert!(matches!(
+            since_from("99999999999d", now),
+            Err(SinceError::OutOfRange(_))
+        ));
+        assert!(matches!(
+            since_from("9223372036854775807s", now),
+            Err(SinceError::OutOfRange(_))
+        ));
+
+        let mut printer = printer(LogConfig::default()).since(Some(at("2024-05-01 14:20:00")));
+        assert_eq!(printer.format("before any timestamp"), None);
+        assert_eq!(printer.format("2024-05-01 14:10:00  INFO: old"), None);
+        assert!(printer.format("2024-05-01 14:25:00  INFO: new").is_some());
+        assert!(printer.format("  continued").is_some());
+    }
+}
@@ -0,0 +1,269 @@
+//! The shell's output kept on disk as `f_state_dir/logs/shell.log`, rotated to
+//! `shell.log.1`, `shell.log.2`, … once it grows past the configured size.
+
+use chrono::Local;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use super::log::LogConfig;
+
+const FILE_NAME: &str = "shell.log";
+
+/// How often `--follow` checks the current file for new lines.
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// `shell.log` for `index` 0, `shell.log.<index>` for older files.
+fn log_path(dir: &Path, index: usize) -> PathBuf {
+    match index {
+        0 => dir.join(FILE_NAME),
+        n => dir.join(format!("{}.{}", FILE_NAME, n)),
+    }
+}
+
+pub struct LogFile {
+    dir: PathBuf,
+    max_size: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl LogFile {
+    /// Opens the current log file for appending, or returns `None` if file logging is
+    /// disabled with `maxFiles: 0`.
+    pub fn open(dir: &Path, config: &LogConfig) -> io::Result<Option<Self>> {
+        if config.max_files == 0 {
+            return Ok(None);
+        }
+        fs::create_dir_all(dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path(dir, 0))?;
+        let size = file.metadata()?.len();
+
+        Ok(Some(LogFile {
+            dir: dir.to_path_buf(),
+            max_size: config.max_file_size,
+            max_files: config.max_files,
+            file,
+            size,
+        }))
+    }
+
+    /// Shifts every file up one index, dropping the oldest, and starts a new current file.
+    fn rotate(&mut self) -> io::Result<()> {
+        for index in (0..self.max_files - 1).rev() {
+            let from = log_path(&self.dir, index);
+            if from.exists() {
+                fs::rename(&from, log_path(&self.dir, index + 1))?;
+            }
+        }
+        self.file = File::create(log_path(&self.dir, 0))?;
+        self.size = 0;
+        Ok(())
+    }
+
+    /// Appends `text` prefixed with the current time, rotating first if it would not fit.
+    pub fn write_line(&mut self, text: &str) -> io::Result<()> {
+        let line = format!(
+            "{} {}\n",
+            Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            text
+        );
+        if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+/// The index of a log file name, 0 for `shell.log` and `n` for `shell.log.<n>`.
+fn log_index(name: &str) -> Option<usize> {
+    match name.strip_prefix(FILE_NAME)? {
+        "" => Some(0),
+        rest => rest.strip_prefix('.')?.parse().ok().filter(|n| *n > 0),
+    }
+}
+
+/// Passes every complete line in `reader` to `print`. A partial last line, still being
+/// written, is left unread.
+fn read_lines(reader: &mut BufReader<File>, print: &mut impl FnMut(&str)) -> io::Result<()> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        match line.strip_suffix('\n') {
+            Some(complete) => print(complete),
+            None => {
+                reader.seek_relative(-(line.len() as i64))?;
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Passes every stored line to `print`, oldest first, skipping files missing from the
+/// sequence. Returns the current file positioned where reading stopped, for `follow`.
+pub fn read_all(dir: &Path, mut print: impl FnMut(&str)) -> io::Result<Option<BufReader<File>>> {
+    let mut indices: Vec<usize> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| log_index(entry.file_name().to_str()?))
+        .collect();
+    if indices.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no shell logs in {}", dir.display()),
+        ));
+    }
+    indices.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut current = None;
+    for index in indices {
+        let mut reader = BufReader::new(File::open(log_path(dir, index))?);
+        read_lines(&mut reader, &mut print)?;
+        if index == 0 {
+            current = Some(reader);
+        }
+    }
+    Ok(current)
+}
+
+/// Waits for lines appended to the current file after `read_all` stopped in it, moving on
+/// to the new file when it is rotated. Runs until interrupted.
+pub fn follow(
+    dir: &Path,
+    current: Option<BufReader<File>>,
+    mut print: impl FnMut(&str),
+) -> io::Result<()> {
+    let path = log_path(dir, 0);
+    let mut reader = match current {
+        Some(reader) => reader,
+        None => loop {
+            match File::open(&path) {
+                Ok(file) => break BufReader::new(file),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    thread::sleep(FOLLOW_POLL_INTERVAL)
+                }
+                Err(e) => return Err(e),
+            }
+        },
+    };
+    let mut line = String::new();
+
+    loop {
+        if reader.read_line(&mut line)? > 0 {
+            // A partial line is finished on a later read.
+            if line.ends_with('\n') {
+                print(line.trim_end_matches('\n'));
+                line.clear();
+            }
+            continue;
+        }
+
+        thread::sleep(FOLLOW_POLL_INTERVAL);
+        // Everything left in the old file has been read by now, so switch to the new one.
+        let current = reader.get_ref().metadata()?.ino();
+        let rotated = fs::metadata(&path).is_ok_and(|m| m.ino() != current);
+        if rotated {
+            reader = BufReader::new(File::open(&path)?);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotates_and_reads_back_oldest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = LogConfig {
+            max_file_size: 80,
+            max_files: 3,
+            ..Default::default()
+        };
+
+        let mut file = LogFile::open(dir.path(), &config).unwrap().unwrap();
+        for i in 0..8 {
+            file.write_line(&format!("  INFO: line {}", i)).unwrap();
+        }
+
+        assert!(log_path(dir.path(), 2).exists());
+        assert!(!log_path(dir.path(), 3).exists());
+
+        let mut lines = Vec::new();
+        read_all(dir.path(), |line| lines.push(line.to_string())).unwrap();
+        let messages: Vec<&str> = lines.iter().map(|l| &l[24..]).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "  INFO: line 2",
+                "  INFO: line 3",
+                "  INFO: line 4",
+                "  INFO: line 5",
+                "  INFO: line 6",
+                "  INFO: line 7"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_all_skips_gaps_and_stops_before_partial_line() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(log_path(dir.path(), 3), "three\n").unwrap();
+        fs::write(log_path(dir.path(), 1), "one\n").unwrap();
+        fs::write(log_path(dir.path(), 0), "zero\nhal").unwrap();
+        fs::write(dir.path().join("shell.log.old"), "ignored\n").unwrap();
+
+        let mut lines = Vec::new();
+        let current = read_all(dir.path(), |line| lines.push(line.to_string())).unwrap();
+        assert_eq!(lines, vec!["three", "one", "zero"]);
+
+        let mut rest = String::new();
+        current.unwrap().read_line(&mut rest).unwrap();
+        assert_eq!(rest, "hal");
+    }
+
+    #[test]
+    fn test_follow_picks_up_lines_written_after_read_all() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(log_path(dir.path(), 0), "first\n").unwrap();
+
+        let current = read_all(dir.path(), |_| {}).unwrap();
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(log_path(dir.path(), 0))
+            .unwrap();
+        file.write_all(b"second\n").unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let path = dir.path().to_path_buf();
+        // follow never returns; the thread ends with the test process.
+        thread::spawn(move || follow(&path, current, |line| tx.send(line.to_string()).unwrap()));
+
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), "second");
+    }
+
+    #[test]
+    fn test_disabled_with_zero_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = LogConfig {
+            max_files: 0,
+            ..Default::default()
+        };
+
+        assert!(LogFile::open(dir.path(), &config).unwrap().is_none());
+        assert!(read_all(dir.path(), |_| {}).is_err());
+    }
+}
@@ -0,0 +1,309 @@
+use chrono::NaiveDateTime;
+use clap::{Args, Subcommand};
+use serde_json::Value;
+use std::env;
+use std::error::Error;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::utils::config::load_user_config;
+use crate::utils::launcher::command_exists;
+use crate::utils::notify::NotifyConfig;
+use crate::utils::paths::Paths;
+use crate::utils::qs::{self, CONFIG_NAME, QsError};
+
+use super::Runnable;
+
+mod log;
+mod logfile;
+mod status;
+mod supervise;
+
+use log::{LogConfig, LogPrinter};
+use logfile::LogFile;
+use supervise::SuperviseConfig;
+
+/// How long `--daemon` watches the detached shell for failing to start.
+const DAEMON_STARTUP_GRACE: Duration = Duration::from_secs(1);
+
+const DAEMON_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Args, Debug)]
+pub struct ShellCmd {
+    #[command(subcommand)]
+    pub command: Option<ShellSubcommand>,
+
+    /// IPC call to make, as `<target> <function> [args…]`; put `--` before a target named
+    /// `ipc`
+    #[arg(trailing_var_arg = true)]
+    pub message: Vec<String>,
+
+    #[arg(short, long)]
+    pub daemon: bool,
+
+    #[arg(short, long)]
+    pub show: bool,
+
+    #[arg(short, long)]
+    pub log: bool,
+
+    /// Keep printing new log lines as the shell writes them
+    #[arg(long, requires = "log")]
+    pub follow: bool,
+
+    /// Only show log lines since a time (`14:30`, `2024-05-01 14:30`) or for a duration (`10m`)
+    #[arg(long, requires = "log", value_name = "TIME", value_parser = log::parse_since)]
+    pub since: Option<NaiveDateTime>,
+
+    /// Read the logs ferret saved instead of asking the running shell
+    #[arg(long, requires = "log")]
+    pub file: bool,
+
+    /// Report whether the shell is running and answering IPC; exits with 3 when it is not
+    /// running and 4 when IPC fails
+    #[arg(long, conflicts_with_all = ["daemon", "log", "kill", "supervise"])]
+    pub status: bool,
+
+    /// Print log lines or the status as JSON
+    #[arg(long)]
+    pub json: bool,
+
+    #[arg(short, long)]
+    pub kill: bool,
+
+    /// Keep the shell running, restarting it with backoff when it crashes
+    #[arg(long, conflicts_with = "daemon")]
+    pub supervise: bool,
+
+    #[arg(long = "log-rules", value_name = "RULES")]
+    pub log_rules: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ShellSubcommand {
+    /// Talk to the running shell over IPC
+    Ipc {
+        #[command(subcommand)]
+        command: IpcSubcommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum IpcSubcommand {
+    /// List IPC targets with their function signatures
+    List {
+        /// Print the targets as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Call a function on an IPC target
+    Call {
+        target: String,
+        function: String,
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+}
+
+fn ipc_call(target: &str, function: &str, args: &[String]) -> Result<(), Box<dyn Error>> {
+    if let Some(result) = qs::call(target, function, args)? {
+        println!("{}", result);
+    }
+    Ok(())
+}
+
+fn ipc_list(json: bool) -> Result<(), Box<dyn Error>> {
+    let targets = qs::targets()?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&targets)?);
+    } else {
+        for target in &targets {
+            print!("{}", target);
+        }
+    }
+    Ok(())
+}
+
+impl ShellCmd {
+    /// The printer for shell output; `persist` also keeps every line in the log files.
+    fn log_printer(&self, user: Option<&Value>, paths: &Paths, persist: bool) -> LogPrinter {
+        let config = LogConfig::from_user(user, paths);
+        let file = if persist {
+            LogFile::open(&paths.shell_logs_dir, &config).unwrap_or_else(|e| {
+                eprintln!("ferret: warning: could not open shell log: {}", e);
+                None
+            })
+        } else {
+            None
+        };
+
+        LogPrinter::new(&config, paths)
+            .file(file)
+            .json(self.json)
+            .colour(!self.json && log::colour_enabled())
+            .since(self.since)
+    }
+
+    fn print_log_files(&self, paths: &Paths) -> Result<(), Box<dyn Error>> {
+        let mut printer = self.log_printer(load_user_config(paths).as_ref(), paths, false);
+        let mut print = |line: &str| {
+            let _ = printer.print(line);
+        };
+
+        let current = logfile::read_all(&paths.shell_logs_dir, &mut print)?;
+        if self.follow {
+            logfile::follow(&paths.shell_logs_dir, current, &mut print)?;
+        }
+        Ok(())
+    }
+
+    fn print_log(&self, paths: &Paths) -> Result<(), Box<dyn Error>> {
+        let mut cmd = Command::new("qs");
+        cmd.args(["-c", CONFIG_NAME, "log"]);
+        if let Some(rules) = &self.log_rules {
+            cmd.args(["-r", rules]);
+        }
+        if self.follow {
+            cmd.arg("-f");
+        }
+        // Timestamps are needed to filter by time and are worth keeping in JSON.
+        if self.since.is_some() || self.json {
+            cmd.arg("-t");
+        }
+
+        // qs reports its own errors on stderr, which is left alone.
+        let mut child = cmd.stdout(Stdio::piped()).spawn()?;
+        log::forward(
+            &mut child,
+            &Mutex::new(self.log_printer(load_user_config(paths).as_ref(), paths, false)),
+            |_| {},
+        )?;
+
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(format!("qs log failed ({})", status).into());
+        }
+        Ok(())
+    }
+
+    fn shell_command(&self) -> Command {
+        let mut cmd = Command::new("qs");
+        cmd.args(["-c", CONFIG_NAME, "-n"]);
+
+        if let Some(rules) = &self.log_rules {
+            cmd.args(["--log-rules", rules]);
+        }
+        cmd
+    }
+
+    fn start_shell(&self, paths: &Paths) -> Result<(), Box<dyn Error>> {
+        if self.daemon {
+            if !command_exists("qs") {
+                return Err("qs is not installed".into());
+            }
+
+            // Rather than `qs -d`, which drops the output, a detached ferret keeps running
+            // the shell in the foreground so its logs still reach the log files.
+            let mut args = vec!["shell".to_string()];
+            if let Some(rules) = &self.log_rules {
+                args.extend(["--log-rules".to_string(), rules.clone()]);
+            }
+            let mut child = Command::new(env::current_exe()?)
+                .args(args)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .process_group(0)
+                .spawn()?;
+
+            // A shell that can't start, e.g. with a broken config, exits within moments.
+            let deadline = Instant::now() + DAEMON_STARTUP_GRACE;
+            while Instant::now() < deadline {
+                if let Some(status) = child.try_wait()? {
+                    if !status.success() {
+                        return Err(format!(
+                            "the shell exited while starting ({}), see `ferret shell --log --file`",
+                            status
+                        )
+                        .into());
+                    }
+                    break;
+                }
+                thread::sleep(DAEMON_POLL_INTERVAL);
+            }
+        } else {
+            let mut child = self
+                .shell_command()
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()?;
+            log::forward(
+                &mut child,
+                &Mutex::new(self.log_printer(load_user_config(paths).as_ref(), paths, true)),
+                |_| {},
+            )?;
+            let status = child.wait()?;
+            if !status.success() {
+                return Err(format!("the shell exited ({})", status).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Runnable<&Paths> for ShellCmd {
+    fn run(&self, paths: &Paths) -> Result<(), Box<dyn Error>> {
+        if let Some(ShellSubcommand::Ipc { command }) = &self.command {
+            return match command {
+                IpcSubcommand::List { json } => ipc_list(*json),
+                IpcSubcommand::Call {
+                    target,
+                    function,
+                    args,
+                } => ipc_call(target, function, args),
+            };
+        }
+
+        match self {
+            s if s.show => ipc_list(false)?,
+
+            s if s.status => status::status(s.json, paths)?,
+
+            s if s.log && s.file => s.print_log_files(paths)?,
+
+            s if s.log => s.print_log(paths)?,
+
+            s if s.kill => {
+                let supervised = supervise::request_stop(paths)?;
+                match qs::kill() {
+                    // The supervisor may be between restarts with no shell to kill.
+                    Err(QsError::NotRunning) if supervised => {}
+                    other => other?,
+                }
+            }
+
+            s if !s.message.is_empty() => match s.message.as_slice() {
+                [target, function, args @ ..] => ipc_call(target, function, args)?,
+                _ => return Err("expected an IPC call as <target> <function> [args…]".into()),
+            },
+
+            s if s.supervise => {
+                let user = load_user_config(paths);
+                supervise::supervise(
+                    s,
+                    &SuperviseConfig::from_user(user.as_ref(), paths),
+                    &NotifyConfig::from_user(user.as_ref(), paths),
+                    s.log_printer(user.as_ref(), paths, true),
+                    paths,
+                )?;
+            }
+
+            _ => self.start_shell(paths)?,
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+//! `ferret shell --status`: whether the shell is alive and answering IPC.
+
+use serde::Serialize;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{self, Command};
+
+use super::supervise::SupervisorState;
+use crate::utils::paths::Paths;
+use crate::utils::qs::{self, CONFIG_NAME};
+
+/// Exit code when no shell instance is running.
+const EXIT_NOT_RUNNING: i32 = 3;
+
+/// Exit code when the shell runs but does not answer IPC.
+const EXIT_IPC_FAILED: i32 = 4;
+
+/// `/proc/<pid>/stat` times are in clock ticks, which Linux fixes at 100 per second for
+/// userspace.
+const CLOCK_TICKS: f64 = 100.0;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Ipc {
+    pub responding: bool,
+    pub targets: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Status {
+    pub running: bool,
+    pub pid: Option<u32>,
+    /// Seconds since the shell process started.
+    pub uptime: Option<u64>,
+    pub config: PathBuf,
+    pub version: Option<String>,
+    pub ipc: Ipc,
+    pub supervisor: Option<SupervisorState>,
+}
+
+impl Status {
+    pub fn collect(paths: &Paths) -> Self {
+        let pid = qs::instance_pids(CONFIG_NAME).into_iter().min();
+        let ipc = match qs::targets() {
+            Ok(targets) => Ipc {
+                responding: true,
+                targets: targets.len(),
+                error: None,
+            },
+            Err(e) => Ipc {
+                responding: false,
+                targets: 0,
+                error: Some(e.to_string()),
+            },
+        };
+
+        Status {
+            // The socket may be reachable without a discoverable process, e.g. when
+            // FERRET_QS_SOCKET points at it.
+            running: pid.is_some() || ipc.responding,
+            pid,
+            uptime: pid.and_then(process_uptime),
+            config: paths.shell_config_dir.clone(),
+            version: qs_version(),
+            ipc,
+            supervisor: SupervisorState::load(paths).filter(SupervisorState::is_running),
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match (self.running, self.ipc.responding) {
+            (false, _) => EXIT_NOT_RUNNING,
+            (true, false) => EXIT_IPC_FAILED,
+            (true, true) => 0,
+        }
+    }
+
+    pub fn print(&self) {
+        match (self.running, self.pid, self.uptime) {
+            (false, _, _) => println!("shell: not running"),
+            (true, Some(pid), Some(uptime)) => println!(
+                "shell: running (pid {}, up {})",
+                pid,
+                format_duration(uptime)
+            ),
+            (true, Some(pid), None) => println!("shell: running (pid {})", pid),
+            (true, None, _) => println!("shell: running"),
+        }
+        println!("config: {}", self.config.display());
+        println!(
+            "version: {}",
+            self.version
+                .as_deref()
+                .unwrap_or("unknown (qs --version failed)")
+        );
+        match &self.ipc.error {
+            None => println!("ipc: ok ({} targets)", self.ipc.targets),
+            Some(e) => println!("ipc: failing ({})", e),
+        }
+        if let Some(supervisor) = &self.supervisor {
+            println!(
+                "supervisor: running (pid {}, {} restarts, {} crashes)",
+                supervisor.pid.unwrap_or_default(),
+                supervisor.restarts,
+                supervisor.total_crashes
+            );
+        }
+    }
+}
+
+/// When `pid` started, in clock ticks since boot. Together with the PID this identifies a
+/// process even after the PID is reused.
+pub fn process_start_ticks(pid: u32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    start_ticks(&stat)
+}
+
+/// Seconds since `pid` started, from its start time in `/proc/<pid>/stat` and the system
+/// uptime.
+fn process_uptime(pid: u32) -> Option<u64> {
+    let started = process_start_ticks(pid)? as f64 / CLOCK_TICKS;
+    let system = fs::read_to_string("/proc/uptime").ok()?;
+    let system: f64 = system.split_whitespace().next()?.parse().ok()?;
+    Some((system - started).max(0.0) as u64)
+}
+
+/// The `starttime` field of a `/proc/<pid>/stat` line. Fields are counted after the
+/// parenthesised command name, which may itself contain spaces.
+fn start_ticks(stat: &str) -> Option<u64> {
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(19)?.parse().ok()
+}
+
+fn qs_version() -> Option<String> {
+    let output = Command::new("qs").arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout.lines().next().map(|line| line.trim().to_string())
+}
+
+/// Uptime as `3d 4h`, `2h 5m`, `5m 12s` or `12s`.
+fn format_duration(secs: u64) -> String {
+    let (days, hours, mins, secs) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    match (days, hours, mins) {
+        (0, 0, 0) => format!("{}s", secs),
+        (0, 0, _) => format!("{}m {}s", mins, secs),
+        (0, _, _) => format!("{}h {}m", hours, mins),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
+/// Prints the status and exits with a code scripts can check: 0 when the shell is healthy,
+/// 3 when it is not running and 4 when it runs but IPC fails.
+pub fn status(json: bool, paths: &Paths) -> Result<(), Box<dyn Error>> {
+    let status = Status::collect(paths);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&status)?);
+    } else {
+        status.print();
+    }
+
+    match status.exit_code() {
+        0 => Ok(()),
+        code => process::exit(code),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_start_ticks_skips_command_name() {
+        let stat = "4242 (qs (ferret) x) S 1 4242 4242 0 -1 4194560 7000 0 0 0 120 30 0 0 20 0 \
+                    12 0 987654 1000000 5000";
+        assert_eq!(start_ticks(stat), Some(987654));
+        assert_eq!(start_ticks("garbage"), None);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(12), "12s");
+        assert_eq!(format_duration(312), "5m 12s");
+        assert_eq!(format_duration(7500), "2h 5m");
+        assert_eq!(format_duration(273600), "3d 4h");
+    }
+}
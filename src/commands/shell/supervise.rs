@@ -0,0 +1,315 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fs;
+use std::process::{self, ExitStatus, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use super::ShellCmd;
+use super::log::{self, LogPrinter};
+use super::status::process_start_ticks;
+use crate::utils::config::ConfigIssue;
+use crate::utils::notify::{Category, Notification, NotifyConfig, Urgency, notify};
+use crate::utils::paths::{Paths, atomic_dump, try_lock_file};
+
+/// Lines of shell stderr kept for the state file and crash notifications.
+const STDERR_TAIL_LINES: usize = 50;
+
+/// How often the stop request is checked while waiting to restart.
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The `shell.supervise` section of `cli.json`. Durations are in seconds.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SuperviseConfig {
+    pub initial_backoff: f64,
+    pub max_backoff: f64,
+    pub stable_after: f64,
+    pub notify_after: u32,
+}
+
+impl Default for SuperviseConfig {
+    fn default() -> Self {
+        SuperviseConfig {
+            initial_backoff: 1.0,
+            max_backoff: 60.0,
+            stable_after: 30.0,
+            notify_after: 3,
+        }
+    }
+}
+
+impl SuperviseConfig {
+    pub fn from_user(user: Option<&Value>, paths: &Paths) -> Self {
+        let Some(section) = user.and_then(|v| v.pointer("/shell/supervise")) else {
+            return SuperviseConfig::default();
+        };
+        let config: Self = serde_json::from_value(section.clone()).unwrap_or_else(|e| {
+            ConfigIssue::new(&paths.user_config_path, "/shell/supervise", e.to_string()).warn();
+            SuperviseConfig::default()
+        });
+        config.checked(paths)
+    }
+
+    /// Resets durations that don't fit a `Duration`, such as negative or huge ones, to
+    /// their defaults.
+    fn checked(mut self, paths: &Paths) -> Self {
+        let default = SuperviseConfig::default();
+        for (key, value, fallback) in [
+            (
+                "initialBackoff",
+                &mut self.initial_backoff,
+                default.initial_backoff,
+            ),
+            ("maxBackoff", &mut self.max_backoff, default.max_backoff),
+            ("stableAfter", &mut self.stable_after, default.stable_after),
+        ] {
+            if Duration::try_from_secs_f64(*value).is_err() {
+                ConfigIssue::new(
+                    &paths.user_config_path,
+                    format!("/shell/supervise/{}", key),
+                    format!(
+                        "{} is not a usable number of seconds, using {}",
+                        value, fallback
+                    ),
+                )
+                .warn();
+                *value = fallback;
+            }
+        }
+        self
+    }
+
+    /// Delay before restarting after `streak` consecutive crashes.
+    fn backoff(&self, streak: u32) -> Duration {
+        let factor = 2f64.powi(streak.saturating_sub(1).min(30) as i32);
+        Duration::from_secs_f64((self.initial_backoff * factor).min(self.max_backoff))
+    }
+}
+
+/// What the supervisor records in `f_state_dir/shell/supervisor.json`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SupervisorState {
+    /// PID of the supervisor while it runs.
+    pub pid: Option<u32>,
+    /// Start time of `pid` in clock ticks since boot, to tell it from a reused PID.
+    pub pid_start: Option<u64>,
+    pub started_at: u64,
+    pub restarts: u32,
+    /// Crashes since the shell last stayed up for `stableAfter`.
+    pub crash_streak: u32,
+    pub total_crashes: u32,
+    pub last_crash_at: Option<u64>,
+    /// How the shell last ended, e.g. `exit status: 1`.
+    pub last_exit: Option<String>,
+    pub last_stderr: Vec<String>,
+}
+
+impl SupervisorState {
+    pub fn load(paths: &Paths) -> Option<Self> {
+        let content = fs::read_to_string(&paths.supervisor_state_path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn save(&self, paths: &Paths) {
+        if let Err(e) = atomic_dump(&paths.supervisor_state_path, self) {
+            eprintln!("ferret: warning: could not save supervisor state: {}", e);
+        }
+    }
+
+    /// Whether the recorded supervisor process is still alive, and not another process that
+    /// got its PID.
+    pub fn is_running(&self) -> bool {
+        self.pid.is_some_and(|pid| {
+            self.pid_start.is_some() && process_start_ticks(pid) == self.pid_start
+        })
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Asks a running supervisor to stop restarting the shell. Returns false if none runs.
+pub fn request_stop(paths: &Paths) -> Result<bool, Box<dyn Error>> {
+    if !SupervisorState::load(paths).is_some_and(|s| s.is_running()) {
+        return Ok(false);
+    }
+    fs::create_dir_all(&paths.shell_state_dir)?;
+    fs::write(&paths.supervisor_stop_path, "")?;
+    Ok(true)
+}
+
+fn stop_requested(paths: &Paths) -> bool {
+    paths.supervisor_stop_path.exists()
+}
+
+/// Runs the shell once, forwarding its output, and returns how it ended with the tail of
+/// its stderr.
+fn run_once(
+    shell: &ShellCmd,
+    printer: &Mutex<LogPrinter>,
+) -> Result<(ExitStatus, Vec<String>), Box<dyn Error>> {
+    let mut child = shell
+        .shell_command()
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let mut tail = VecDeque::with_capacity(STDERR_TAIL_LINES);
+    log::forward(&mut child, printer, |line| {
+        if tail.len() == STDERR_TAIL_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line.to_string());
+    })?;
+
+    let status = child.wait()?;
+    Ok((status, tail.into()))
+}
+
+/// Sleeps for `delay`, returning early (with true) if a stop is requested meanwhile.
+fn wait_or_stop(delay: Duration, paths: &Paths) -> bool {
+    let deadline = Instant::now() + delay;
+    while Instant::now() < deadline {
+        if stop_requested(paths) {
+            return true;
+        }
+        thread::sleep(STOP_POLL_INTERVAL.min(deadline - Instant::now()));
+    }
+    stop_requested(paths)
+}
+
+/// Keeps the shell running: restarts it with exponential backoff whenever it exits with a
+/// failure, until it exits cleanly or `ferret shell --kill` asks the supervisor to stop.
+pub fn supervise(
+    shell: &ShellCmd,
+    config: &SuperviseConfig,
+    notify_config: &NotifyConfig,
+    printer: LogPrinter,
+    paths: &Paths,
+) -> Result<(), Box<dyn Error>> {
+    let Some(_lock) = try_lock_file(&paths.supervisor_lock_path)? else {
+        let pid = SupervisorState::load(paths).and_then(|s| s.pid);
+        return Err(match pid {
+            Some(pid) => format!("the shell is already supervised (pid {})", pid),
+            None => "the shell is already supervised".to_string(),
+        }
+        .into());
+    };
+
+    let printer = Mutex::new(printer);
+    let _ = fs::remove_file(&paths.supervisor_stop_path);
+
+    let mut state = SupervisorState {
+        pid: Some(process::id()),
+        pid_start: process_start_ticks(process::id()),
+        started_at: now(),
+        ..Default::default()
+    };
+    state.save(paths);
+    // The crash notification, updated in place while the streak goes on.
+    let mut crash_notification = None;
+
+    let result = loop {
+        let started = Instant::now();
+        let (status, stderr) = match run_once(shell, &printer) {
+            Ok(outcome) => outcome,
+            Err(e) => break Err(e),
+        };
+
+        state.last_exit = Some(status.to_string());
+        if status.success() || stop_requested(paths) {
+            break Ok(());
+        }
+
+        if started.elapsed() >= Duration::from_secs_f64(config.stable_after) {
+            state.crash_streak = 0;
+            crash_notification = None;
+        }
+        state.crash_streak += 1;
+        state.total_crashes += 1;
+        state.last_crash_at = Some(now());
+        state.last_stderr = stderr;
+
+        if config.notify_after > 0 && state.crash_streak >= config.notify_after {
+            let last_line = state.last_stderr.last().cloned().unwrap_or_default();
+            let mut notification = Notification::new(format!(
+                "Shell crashed {} times in a row",
+                state.crash_streak
+            ))
+            .category(Category::Shell)
+            .body(last_line)
+            .urgency(Urgency::Critical);
+            if let Some(id) = crash_notification {
+                notification = notification.replaces(id);
+            }
+            // Best effort: the crash is also on stderr and in the state file.
+            if let Ok(Some(id)) = notify(&notification, notify_config) {
+                crash_notification = Some(id);
+            }
+        }
+
+        let delay = config.backoff(state.crash_streak);
+        eprintln!(
+            "ferret: shell exited ({}), restarting in {:.1}s",
+            status,
+            delay.as_secs_f64()
+        );
+        state.save(paths);
+
+        if wait_or_stop(delay, paths) {
+            break Ok(());
+        }
+        state.restarts += 1;
+    };
+
+    state.pid = None;
+    state.pid_start = None;
+    state.save(paths);
+    let _ = fs::remove_file(&paths.supervisor_stop_path);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let config = SuperviseConfig {
+            initial_backoff: 1.0,
+            max_backoff: 10.0,
+            ..Default::default()
+        };
+
+        assert_eq!(config.backoff(1), Duration::from_secs(1));
+        assert_eq!(config.backoff(2), Duration::from_secs(2));
+        assert_eq!(config.backoff(4), Duration::from_secs(8));
+        assert_eq!(config.backoff(5), Duration::from_secs(10));
+        assert_eq!(config.backoff(500), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_is_running_checks_start_time() {
+        let pid = process::id();
+        let start = process_start_ticks(pid);
+        let state = |pid_start| SupervisorState {
+            pid: Some(pid),
+            pid_start,
+            ..Default::default()
+        };
+
+        assert!(start.is_some());
+        assert!(state(start).is_running());
+        assert!(!state(start.map(|s| s + 1)).is_running());
+        assert!(!state(None).is_running());
+    }
+}
@@ -0,0 +1,205 @@
+use serde::{Deserialize, Deserializer};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::utils::config::ConfigIssue;
+use crate::utils::matcher::Rule;
+use crate::utils::paths::Paths;
+
+/// Where an effective toggle entry came from, as shown by `ferret toggle --list`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Origin {
+    #[default]
+    Default,
+    User,
+    /// A user entry replacing a built-in default of the same name.
+    Override,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Origin::Default => "default",
+            Origin::User => "user",
+            Origin::Override => "user, overrides default",
+        })
+    }
+}
+
+/// A window dimension in toggle configs: pixels, or a percentage of the monitor like `"60%"`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dimension {
+    Pixels(i32),
+    Percent(f64),
+}
+
+impl Dimension {
+    /// Pixel size for a monitor `extent` (in logical pixels) wide or high.
+    pub fn resolve(&self, extent: i32) -> i32 {
+        match self {
+            Dimension::Pixels(px) => *px,
+            Dimension::Percent(pct) => (extent as f64 * pct / 100.0).round() as i32,
+        }
+    }
+}
+
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dimension::Pixels(px) => write!(f, "{}", px),
+            Dimension::Percent(pct) => write!(f, "{}%", pct),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Dimension {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Pixels(i32),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Pixels(px) if px >= 1 => Ok(Dimension::Pixels(px)),
+            Raw::Pixels(px) => Err(serde::de::Error::custom(format!(
+                "invalid size {}, expected at least 1 pixel",
+                px
+            ))),
+            Raw::Text(text) => text
+                .strip_suffix('%')
+                .and_then(|pct| pct.trim().parse::<f64>().ok())
+                .filter(|pct| *pct > 0.0 && *pct <= 100.0)
+                .map(Dimension::Percent)
+                .ok_or_else(|| {
+                    serde::de::Error::custom(format!(
+                        "invalid size {:?}, expected pixels or a percentage like \"60%\"",
+                        text
+                    ))
+                }),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ClientConfig {
+    #[serde(default)]
+    pub enable: bool,
+    #[serde(rename = "match")]
+    pub matches: Option<Vec<Rule>>,
+    pub command: Option<Vec<String>>,
+    #[serde(rename = "move")]
+    pub should_move: Option<bool>,
+    pub float: Option<bool>,
+    /// Width and height, applied to floating windows.
+    pub size: Option<[Dimension; 2]>,
+    #[serde(default)]
+    pub center: bool,
+    pub pin: Option<bool>,
+    /// Monitor name or ID that sizes and positions are relative to; the focused one otherwise.
+    pub monitor: Option<String>,
+    #[serde(skip)]
+    pub origin: Origin,
+}
+
+pub type ClientGroup = HashMap<String, ClientConfig>;
+pub type FullConfig = HashMap<String, ClientGroup>;
+
+/// Reserved key in `toggles` that controls whether the built-in groups are kept.
+const INHERIT_DEFAULTS_KEY: &str = "inheritDefaults";
+
+fn get_default_config() -> FullConfig {
+    let j = json!({
+        "communication": {
+            "discord": { "enable": true, "match": [{"class": "discord"}], "command": ["discord"], "move": true },
+            "whatsapp": { "enable": true, "match": [{"class": "whatsapp"}], "move": true }
+        },
+        "music": {
+            "spotify": {
+                "enable": true,
+                "match": [{"class": "Spotify"}, {"initialTitle": "Spotify"}, {"initialTitle": "Spotify Free"}],
+                "command": ["spicetify", "watch", "-s"],
+                "move": true
+            },
+            "feishin": { "enable": true, "match": [{"class": "feishin"}], "move": true }
+        },
+        "sysmon": {
+            "btop": {
+                "enable": true,
+                "match": [{"class": "btop", "title": "btop", "workspace": {"name": "special:sysmon"}}],
+                "command": ["foot", "-a", "btop", "-T", "btop", "fish", "-C", "exec btop"]
+            }
+        },
+        "todo": {
+            "todoist": { "enable": true, "match": [{"class": "Todoist"}], "command": ["todoist"], "move": true }
+        }
+    });
+    serde_json::from_value(j).unwrap()
+}
+
+/// Merges the user `toggles` over the built-in defaults. A `null` group or client removes
+/// the default of that name, and `"inheritDefaults": false` drops all defaults.
+pub fn load_config(user: Option<&Value>, paths: &Paths) -> FullConfig {
+    let toggles = user
+        .and_then(|v| v.get("toggles"))
+        .and_then(|v| v.as_object());
+
+    let inherit = toggles
+        .and_then(|t| t.get(INHERIT_DEFAULTS_KEY))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+
+    let mut config = if inherit {
+        get_default_config()
+    } else {
+        FullConfig::new()
+    };
+
+    let Some(toggles) = toggles else {
+        return config;
+    };
+
+    for (group_name, group_val) in toggles {
+        if group_name == INHERIT_DEFAULTS_KEY {
+            continue;
+        }
+
+        let group_obj = match group_val {
+            Value::Null => {
+                config.remove(group_name);
+                continue;
+            }
+            Value::Object(obj) => obj,
+            _ => continue,
+        };
+
+        let target_group = config.entry(group_name.clone()).or_default();
+
+        for (client_name, client_val) in group_obj {
+            if client_val.is_null() {
+                target_group.remove(client_name);
+                continue;
+            }
+
+            match serde_json::from_value::<ClientConfig>(client_val.clone()) {
+                Ok(mut client_cfg) => {
+                    client_cfg.origin = if target_group.contains_key(client_name) {
+                        Origin::Override
+                    } else {
+                        Origin::User
+                    };
+                    target_group.insert(client_name.clone(), client_cfg);
+                }
+                Err(e) => ConfigIssue::new(
+                    &paths.user_config_path,
+                    format!("/toggles/{}/{}", group_name, client_name),
+                    format!("ignoring toggle: {}", e),
+                )
+                .warn(),
+            }
+        }
+    }
+    config
+}
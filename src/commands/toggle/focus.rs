@@ -0,0 +1,92 @@
+use super::config::{ClientConfig, ClientGroup};
+use super::selects;
+use crate::utils::hypr::models::Client;
+
+/// A window of a toggle group with the name and config of the client it belongs to.
+pub type GroupWindow<'a> = (&'a str, &'a ClientConfig, &'a Client);
+
+/// Every window matched by an enabled client of the group, ordered by client name and then
+/// address so cycling is stable between runs.
+pub fn group_windows<'a>(group: &'a ClientGroup, clients: &'a [Client]) -> Vec<GroupWindow<'a>> {
+    let mut configs: Vec<_> = group.iter().filter(|(_, cfg)| cfg.enable).collect();
+    configs.sort_by_key(|(name, _)| name.as_str());
+
+    let mut windows: Vec<GroupWindow> = Vec::new();
+    for (name, cfg) in configs {
+        let mut matched: Vec<&Client> = clients.iter().filter(|c| selects(cfg, c)).collect();
+        matched.sort_by_key(|c| c.address.as_str());
+        for client in matched {
+            // A window matched by two configs is only visited once.
+            if !windows.iter().any(|(_, _, w)| w.address == client.address) {
+                windows.push((name.as_str(), cfg, client));
+            }
+        }
+    }
+    windows
+}
+
+/// The window after `active` in `windows`, wrapping around; the first one if `active` isn't
+/// part of the group.
+pub fn next_window<'a>(
+    windows: &'a [GroupWindow<'a>],
+    active: Option<&str>,
+) -> Option<&'a GroupWindow<'a>> {
+    let position = active.and_then(|a| windows.iter().position(|(_, _, c)| c.address == a));
+    match position {
+        Some(i) => windows.get((i + 1) % windows.len()),
+        None => windows.first(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn client(address: &str, class: &str) -> Client {
+        serde_json::from_value(json!({
+            "address": address, "at": [0, 0], "size": [400, 300],
+            "workspace": { "id": -98, "name": "special:communication" }, "floating": false,
+            "monitor": 0, "class": class, "title": class, "initialClass": class,
+            "initialTitle": class, "pid": 1, "xwayland": false, "pinned": false
+        }))
+        .unwrap()
+    }
+
+    fn group() -> ClientGroup {
+        serde_json::from_value(json!({
+            "whatsapp": { "enable": true, "match": [{"class": "whatsapp"}] },
+            "discord": { "enable": true, "match": [{"class": "discord"}] },
+            "slack": { "enable": false, "match": [{"class": "slack"}] }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_group_windows_are_ordered_and_skip_disabled() {
+        let clients = vec![
+            client("0x3", "whatsapp"),
+            client("0x2", "discord"),
+            client("0x1", "discord"),
+            client("0x4", "slack"),
+        ];
+        let group = group();
+        let windows = group_windows(&group, &clients);
+        let addresses: Vec<&str> = windows.iter().map(|(_, _, c)| c.address.as_str()).collect();
+
+        assert_eq!(addresses, vec!["0x1", "0x2", "0x3"]);
+    }
+
+    #[test]
+    fn test_next_window_wraps() {
+        let clients = vec![client("0x1", "discord"), client("0x2", "whatsapp")];
+        let group = group();
+        let windows = group_windows(&group, &clients);
+        let next = |active| next_window(&windows, active).map(|(_, _, c)| c.address.as_str());
+
+        assert_eq!(next(Some("0x1")), Some("0x2"));
+        assert_eq!(next(Some("0x2")), Some("0x1"));
+        assert_eq!(next(Some("0xdead")), Some("0x1"));
+        assert_eq!(next(None), Some("0x1"));
+    }
+}
@@ -0,0 +1,467 @@
+use clap::Args;
+use std::error::Error;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::utils::config::load_user_config;
+use crate::utils::hypr::{
+    self,
+    models::{Client, Monitor},
+};
+use crate::utils::launcher::{self, Launcher, LauncherConfig};
+use crate::utils::notify::{Category, Notification, Notifier, NotifyConfig, Urgency, notify};
+use crate::utils::paths::{Paths, lock_file};
+
+mod config;
+mod focus;
+mod placement;
+mod specialws;
+
+use config::{ClientConfig, ClientGroup, FullConfig, load_config};
+
+/// The longest `--wait-timeout` accepted, a day.
+const MAX_WAIT_TIMEOUT: f64 = 86400.0;
+
+/// Value parser for `--wait-timeout`: finite seconds between zero and a day.
+fn parse_wait_timeout(s: &str) -> Result<f64, String> {
+    let secs: f64 = s
+        .parse()
+        .map_err(|e: std::num::ParseFloatError| e.to_string())?;
+    if !(0.0..=MAX_WAIT_TIMEOUT).contains(&secs) {
+        return Err(format!(
+            "must be between 0 and {} seconds",
+            MAX_WAIT_TIMEOUT
+        ));
+    }
+    Ok(secs)
+}
+
+#[derive(Args, Debug)]
+pub struct ToggleCmd {
+    /// Toggle group to show, or `specialws` for the focused monitor's special workspace
+    #[arg(required_unless_present_any = ["list", "prestart"])]
+    pub workspace: Option<String>,
+
+    /// Show the effective toggle config and where each entry came from
+    #[arg(long)]
+    pub list: bool,
+
+    /// Start the apps of every group, or only the given one, without showing them
+    #[arg(long, conflicts_with_all = ["list", "focus", "cycle", "wait", "next", "prev"])]
+    pub prestart: bool,
+
+    /// When an app is started, wait for its window and then show the workspace
+    #[arg(long)]
+    pub wait: bool,
+
+    /// How long --wait and --prestart wait for windows to appear
+    #[arg(long, value_name = "SECONDS", default_value_t = 10.0, value_parser = parse_wait_timeout)]
+    pub wait_timeout: f64,
+
+    /// Focus the window of this client from the group, showing the workspace if needed
+    #[arg(long, value_name = "CLIENT", conflicts_with = "cycle")]
+    pub focus: Option<String>,
+
+    /// Focus the next window of the group
+    #[arg(long)]
+    pub cycle: bool,
+
+    /// With specialws, switch to the next open special workspace
+    #[arg(long, conflicts_with = "prev")]
+    pub next: bool,
+
+    /// With specialws, switch to the previous open special workspace
+    #[arg(long)]
+    pub prev: bool,
+}
+
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+fn print_config(config: &FullConfig) {
+    let mut groups: Vec<_> = config.iter().collect();
+    groups.sort_by_key(|(name, _)| name.as_str());
+
+    for (group_name, group) in groups {
+        println!("{}", group_name);
+
+        let mut clients: Vec<_> = group.iter().collect();
+        clients.sort_by_key(|(name, _)| name.as_str());
+
+        for (client_name, client) in clients {
+            let mut details = vec![if client.enable { "enabled" } else { "disabled" }.to_string()];
+            if client.should_move.unwrap_or(false) {
+                details.push("move".to_string());
+            }
+            let rules = placement::exec_rules(client);
+            if !rules.is_empty() {
+                details.push(format!("window: {}", rules.join("; ")));
+            }
+            if let Some(cmd) = &client.command {
+                details.push(format!("command: {}", shell_words::join(cmd)));
+            }
+            if let Some(matches) = &client.matches {
+                let rules: Vec<String> = matches.iter().map(|m| m.to_string()).collect();
+                details.push(format!("match: {}", rules.join(" | ")));
+            }
+
+            println!("  {} ({})", client_name, client.origin);
+            println!("    {}", details.join(", "));
+        }
+    }
+}
+
+/// Whether a running client matches any of the config's match rules.
+fn selects(client: &ClientConfig, c: &Client) -> bool {
+    let Some(matches) = &client.matches else {
+        return false;
+    };
+    let value = c.to_value();
+    matches.iter().any(|rule| rule.matches(&value))
+}
+
+/// A dispatcher and its argument string, queued up to be sent in one batch.
+type Action = (&'static str, String);
+
+/// Builds `exec` dispatches for toggle clients. The launcher is only resolved once something
+/// needs starting, so a missing launcher doesn't break plain toggling.
+struct Spawner {
+    config: LauncherConfig,
+    launcher: Option<Launcher>,
+}
+
+impl Spawner {
+    fn new(config: LauncherConfig) -> Self {
+        Spawner {
+            config,
+            launcher: None,
+        }
+    }
+
+    /// An `exec` that opens `cmd` on `special:<workspace>`, without switching to it when
+    /// `silent` is set.
+    fn exec_action(
+        &mut self,
+        workspace: &str,
+        client: &ClientConfig,
+        cmd: &[String],
+        silent: bool,
+    ) -> Result<Action, Box<dyn Error>> {
+        let launcher = match &self.launcher {
+            Some(l) => l,
+            None => self.launcher.insert(Launcher::new(&self.config)?),
+        };
+        let line = launcher.command_line(cmd)?;
+
+        let mut rules = vec![format!(
+            "workspace special:{}{}",
+            workspace,
+            if silent { " silent" } else { "" }
+        )];
+        rules.extend(placement::exec_rules(client));
+        Ok(("exec", format!("[{}] {}", rules.join("; "), line)))
+    }
+}
+
+impl ToggleCmd {
+    pub fn run(&self, paths: &Paths) -> Result<(), Box<dyn Error>> {
+        let user = load_user_config(paths);
+
+        if self.list {
+            print_config(&load_config(user.as_ref(), paths));
+            return Ok(());
+        }
+
+        let workspace = self.workspace.as_deref().unwrap_or_default();
+
+        let direction = if self.next {
+            Some(specialws::Direction::Next)
+        } else if self.prev {
+            Some(specialws::Direction::Prev)
+        } else {
+            None
+        };
+
+        let focusing = self.focus.is_some() || self.cycle;
+
+        if workspace == "specialws" {
+            if focusing {
+                return Err("--focus and --cycle need a toggle group".into());
+            }
+            return specialws::toggle(paths, direction);
+        }
+        if direction.is_some() {
+            return Err("--next and --prev only apply to specialws".into());
+        }
+
+        let config = load_config(user.as_ref(), paths);
+        let mut spawner = Spawner::new(LauncherConfig::from_user(user.as_ref(), paths));
+
+        if self.prestart {
+            return self.prestart(&config, &mut spawner, paths);
+        }
+
+        if focusing {
+            let Some(group) = config.get(workspace) else {
+                return Err(format!("unknown toggle group {}", workspace).into());
+            };
+            if self.focus_window(workspace, group)? {
+                return Ok(());
+            }
+        }
+
+        let mut actions: Vec<Action> = Vec::new();
+        let mut spawned = Vec::new();
+
+        if let Some(group) = config.get(workspace) {
+            let clients = self.get_clients()?;
+            let monitors = if group
+                .values()
+                .any(|c| c.enable && c.should_move.unwrap_or(false) && placement::needs_monitor(c))
+            {
+                hypr::monitors()?
+            } else {
+                Vec::new()
+            };
+
+            for (name, client_cfg) in group {
+                if !client_cfg.enable {
+                    continue;
+                }
+                if let Some(cmd) = self.command_to_spawn(name, client_cfg, &clients) {
+                    actions.push(spawner.exec_action(workspace, client_cfg, cmd, false)?);
+                    spawned.push((name.as_str(), client_cfg));
+                }
+                self.queue_moves(client_cfg, workspace, &clients, &monitors, &mut actions);
+            }
+        }
+
+        if spawned.is_empty() {
+            actions.push(("togglespecialworkspace", workspace.to_string()));
+        }
+
+        hypr::batch_dispatch(&actions)?;
+
+        if self.wait && !spawned.is_empty() {
+            let started = spawned.len();
+            let missing = self.wait_for_windows(spawned, |_, _| {})?;
+            let notify_config = NotifyConfig::from_user(user.as_ref(), paths);
+
+            for name in &missing {
+                eprintln!(
+                    "ferret: warning: {} did not open a window within {}s",
+                    name, self.wait_timeout
+                );
+                // Best effort: the warning above already reports the failure.
+                let _ = notify(
+                    &Notification::new(format!("Failed to start {}", name))
+                        .category(Category::Toggle)
+                        .body(format!(
+                            "No window appeared on special:{} within {}s",
+                            workspace, self.wait_timeout
+                        ))
+                        .urgency(Urgency::Critical),
+                    &notify_config,
+                );
+            }
+
+            if missing.len() < started {
+                hypr::dispatch("togglespecialworkspace", &[workspace])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Polls the client list until every spawned config has a matching window or the
+    /// timeout runs out, returning the names of those still missing. `on_progress` gets the
+    /// number of windows open and expected whenever the first one changes.
+    fn wait_for_windows<'a>(
+        &self,
+        mut pending: Vec<(&'a str, &ClientConfig)>,
+        mut on_progress: impl FnMut(usize, usize),
+    ) -> Result<Vec<&'a str>, Box<dyn Error>> {
+        let deadline = Instant::now() + Duration::from_secs_f64(self.wait_timeout);
+        let total = pending.len();
+        let mut reported = None;
+
+        loop {
+            let clients = hypr::clients()?;
+            pending.retain(|(_, cfg)| !clients.iter().any(|c| selects(cfg, c)));
+            let opened = total - pending.len();
+            if reported != Some(opened) {
+                on_progress(opened, total);
+                reported = Some(opened);
+            }
+
+            if pending.is_empty() || Instant::now() >= deadline {
+                break;
+            }
+            thread::sleep(WAIT_POLL_INTERVAL);
+        }
+
+        Ok(pending.into_iter().map(|(name, _)| name).collect())
+    }
+
+    /// Starts every enabled client with a command that isn't already running, leaving its
+    /// workspace hidden. Running it again starts nothing new, so it suits `exec-once`. A lock
+    /// is held until the started windows appear, so a second run doesn't start them again.
+    fn prestart(
+        &self,
+        config: &FullConfig,
+        spawner: &mut Spawner,
+        paths: &Paths,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut groups: Vec<(&String, &ClientGroup)> = match &self.workspace {
+            Some(name) => vec![
+                config
+                    .get_key_value(name)
+                    .ok_or_else(|| format!("unknown toggle group {}", name))?,
+            ],
+            None => config.iter().collect(),
+        };
+        groups.sort_by_key(|(name, _)| name.as_str());
+
+        let _lock = lock_file(&paths.prestart_lock_path)?;
+        let clients = self.get_clients()?;
+        let mut actions: Vec<Action> = Vec::new();
+        let mut spawned = Vec::new();
+
+        for (workspace, group) in groups {
+            let mut entries: Vec<_> = group.iter().filter(|(_, c)| c.enable).collect();
+            entries.sort_by_key(|(name, _)| name.as_str());
+
+            for (name, client_cfg) in entries {
+                if let Some(cmd) = self.command_to_spawn(name, client_cfg, &clients) {
+                    actions.push(spawner.exec_action(workspace, client_cfg, cmd, true)?);
+                    spawned.push((name.as_str(), client_cfg));
+                }
+            }
+        }
+
+        hypr::batch_dispatch(&actions)?;
+
+        if spawned.is_empty() {
+            return Ok(());
+        }
+
+        let user = load_user_config(paths);
+        // Best effort: the apps start the same without a notification server.
+        let notifier = Notifier::connect(&NotifyConfig::from_user(user.as_ref(), paths)).ok();
+        let mut progress = None;
+        let missing = self.wait_for_windows(spawned, |opened, total| {
+            let Some(notifier) = &notifier else {
+                return;
+            };
+            let mut notification = Notification::new("Starting apps")
+                .category(Category::Toggle)
+                .urgency(Urgency::Low)
+                .body(format!("{} of {} open", opened, total))
+                .value((opened * 100 / total) as u8)
+                .persistent();
+            if let Some(id) = progress {
+                notification = notification.replaces(id);
+            }
+            if let Ok(Some(id)) = notifier.send(&notification) {
+                progress = Some(id);
+            }
+        })?;
+        if let (Some(notifier), Some(id)) = (&notifier, progress) {
+            let _ = notifier.close(id);
+        }
+
+        for name in missing {
+            eprintln!(
+                "ferret: warning: {} did not open a window within {}s",
+                name, self.wait_timeout
+            );
+        }
+        Ok(())
+    }
+
+    /// Focuses a window of the group for --focus/--cycle. Returns false when there is none,
+    /// so the group is toggled and its apps started as usual.
+    fn focus_window(&self, workspace: &str, group: &ClientGroup) -> Result<bool, Box<dyn Error>> {
+        let clients = self.get_clients()?;
+        let windows = focus::group_windows(group, &clients);
+
+        let target = match &self.focus {
+            Some(name) => {
+                if !group.contains_key(name) {
+                    return Err(format!("no client {} in toggle group {}", name, workspace).into());
+                }
+                windows.iter().find(|(n, _, _)| n == name)
+            }
+            None => {
+                let active = hypr::active_window()?;
+                focus::next_window(&windows, active.as_ref().map(|c| c.address.as_str()))
+            }
+        };
+        let Some((_, cfg, window)) = target else {
+            return Ok(false);
+        };
+
+        let mut actions: Vec<Action> = Vec::new();
+        if cfg.should_move.unwrap_or(false)
+            && window.workspace.name != format!("special:{}", workspace)
+        {
+            let arg = format!("special:{},address:{}", workspace, window.address);
+            actions.push(("movetoworkspacesilent", arg));
+        }
+        actions.push(("focuswindow", format!("address:{}", window.address)));
+
+        hypr::batch_dispatch(&actions)?;
+        Ok(true)
+    }
+
+    fn get_clients(&self) -> Result<Vec<Client>, Box<dyn Error>> {
+        Ok(hypr::clients()?)
+    }
+
+    /// The command to start for a client config, if it has one that isn't already running.
+    /// Commands that can't be found are reported and skipped.
+    fn command_to_spawn<'a>(
+        &self,
+        name: &str,
+        client: &'a ClientConfig,
+        clients: &[Client],
+    ) -> Option<&'a [String]> {
+        let cmd_parts = client.command.as_deref().filter(|c| !c.is_empty())?;
+
+        if clients.iter().any(|c| selects(client, c)) {
+            return None;
+        }
+
+        if !launcher::command_exists(&cmd_parts[0]) {
+            eprintln!(
+                "ferret: warning: {} not found, not starting {}",
+                cmd_parts[0], name
+            );
+            return None;
+        }
+
+        Some(cmd_parts)
+    }
+
+    fn queue_moves(
+        &self,
+        client: &ClientConfig,
+        workspace: &str,
+        clients: &[Client],
+        monitors: &[Monitor],
+        actions: &mut Vec<Action>,
+    ) {
+        if !client.should_move.unwrap_or(false) {
+            return;
+        }
+
+        let target_ws_name = format!("special:{}", workspace);
+        let monitor = placement::target_monitor(client, monitors);
+        for c in clients {
+            if selects(client, c) && c.workspace.name != target_ws_name {
+                let arg = format!("special:{},address:{}", workspace, c.address);
+                actions.push(("movetoworkspacesilent", arg));
+                actions.extend(placement::window_actions(client, c, monitor));
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use super::Action;
+use super::config::ClientConfig;
+use crate::utils::hypr::models::{Client, Monitor};
+
+/// Whether a config needs monitor geometry to place its windows.
+pub fn needs_monitor(cfg: &ClientConfig) -> bool {
+    cfg.size.is_some() || cfg.center
+}
+
+/// The monitor a config's sizes are relative to: the configured one by name or ID, else the
+/// focused one.
+pub fn target_monitor<'a>(cfg: &ClientConfig, monitors: &'a [Monitor]) -> Option<&'a Monitor> {
+    match &cfg.monitor {
+        Some(wanted) => monitors
+            .iter()
+            .find(|m| &m.name == wanted || m.id.to_string() == *wanted),
+        None => monitors.iter().find(|m| m.focused),
+    }
+}
+
+/// Monitor size in layout pixels, accounting for scale and rotation.
+fn logical_size(monitor: &Monitor) -> (i32, i32) {
+    let (w, h) = if monitor.transform % 2 == 1 {
+        (monitor.height, monitor.width)
+    } else {
+        (monitor.width, monitor.height)
+    };
+    let scale = if monitor.scale > 0.0 {
+        monitor.scale
+    } else {
+        1.0
+    };
+    (
+        (w as f64 / scale).round() as i32,
+        (h as f64 / scale).round() as i32,
+    )
+}
+
+/// Window rules for the `exec` dispatch, so spawned windows open already placed.
+pub fn exec_rules(cfg: &ClientConfig) -> Vec<String> {
+    let mut rules = Vec::new();
+    if let Some(float) = cfg.float {
+        rules.push(if float { "float" } else { "tile" }.to_string());
+    }
+    if let Some([w, h]) = cfg.size {
+        rules.push(format!("size {} {}", w, h));
+    }
+    if cfg.center {
+        rules.push("center".to_string());
+    }
+    if cfg.pin == Some(true) {
+        rules.push("pin".to_string());
+    }
+    if let Some(monitor) = &cfg.monitor {
+        rules.push(format!("monitor {}", monitor));
+    }
+    rules
+}
+
+/// Dispatches that float, size, center and pin an existing window as configured.
+pub fn window_actions(
+    cfg: &ClientConfig,
+    client: &Client,
+    monitor: Option<&Monitor>,
+) -> Vec<Action> {
+    let mut actions = Vec::new();
+    let target = format!("address:{}", client.address);
+
+    let floating = cfg.float.unwrap_or(client.floating);
+    match cfg.float {
+        Some(true) if !client.floating => actions.push(("setfloating", target.clone())),
+        Some(false) if client.floating => actions.push(("settiled", target.clone())),
+        _ => {}
+    }
+
+    if floating && let Some(monitor) = monitor {
+        let (mon_w, mon_h) = logical_size(monitor);
+
+        let (w, h) = match cfg.size {
+            Some([w, h]) => {
+                let size = (w.resolve(mon_w), h.resolve(mon_h));
+                actions.push((
+                    "resizewindowpixel",
+                    format!("exact {} {},{}", size.0, size.1, target),
+                ));
+                size
+            }
+            None => (client.size[0], client.size[1]),
+        };
+
+        if cfg.center {
+            let x = monitor.x + (mon_w - w) / 2;
+            let y = monitor.y + (mon_h - h) / 2;
+            actions.push(("movewindowpixel", format!("exact {} {},{}", x, y, target)));
+        }
+    }
+
+    if let Some(pin) = cfg.pin
+        && floating
+        && pin != client.pinned
+    {
+        actions.push(("pin", target));
+    }
+
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(value: serde_json::Value) -> ClientConfig {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn client(floating: bool) -> Client {
+        serde_json::from_value(json!({
+            "address": "0x1", "at": [0, 0], "size": [400, 300],
+            "workspace": { "id": 1, "name": "1" }, "floating": floating, "monitor": 0,
+            "class": "btop", "title": "btop", "initialClass": "btop", "initialTitle": "btop",
+            "pid": 1, "xwayland": false, "pinned": false
+        }))
+        .unwrap()
+    }
+
+    fn monitor(name: &str, x: i32, width: i32, height: i32, scale: f64) -> Monitor {
+        serde_json::from_value(json!({
+            "id": 1, "name": name, "width": width, "height": height, "refreshRate": 60.0,
+            "x": x, "y": 0, "activeWorkspace": { "id": 1, "name": "1" },
+            "specialWorkspace": { "id": 0, "name": "" }, "scale": scale, "focused": false
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_percent_size_and_center_follow_monitor() {
+        let cfg = config(json!({ "float": true, "size": ["50%", "50%"], "center": true }));
+        let mon = monitor("DP-2", 1920, 3840, 2160, 2.0);
+
+        assert_eq!(
+            window_actions(&cfg, &client(false), Some(&mon)),
+            vec![
+                ("setfloating", "address:0x1".to_string()),
+                ("resizewindowpixel", "exact 960 540,address:0x1".to_string()),
+                ("movewindowpixel", "exact 2400 270,address:0x1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pixel_size_and_pin() {
+        let cfg = config(json!({ "size": [800, 600], "pin": true }));
+        let mon = monitor("DP-1", 0, 1920, 1080, 1.0);
+
+        assert_eq!(
+            window_actions(&cfg, &client(true), Some(&mon)),
+            vec![
+                ("resizewindowpixel", "exact 800 600,address:0x1".to_string()),
+                ("pin", "address:0x1".to_string()),
+            ]
+        );
+        assert!(window_actions(&cfg, &client(false), Some(&mon)).is_empty());
+    }
+
+    #[test]
+    fn test_exec_rules() {
+        let cfg = config(json!({
+            "float": true, "size": ["60%", 700], "center": true, "monitor": "DP-1"
+        }));
+        assert_eq!(
+            exec_rules(&cfg),
+            vec!["float", "size 60% 700", "center", "monitor DP-1"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_percent_is_rejected() {
+        let result = serde_json::from_value::<ClientConfig>(json!({ "size": ["150%", "50%"] }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_non_positive_pixels_are_rejected() {
+        for size in [json!([0, 600]), json!([800, -5])] {
+            let result = serde_json::from_value::<ClientConfig>(json!({ "size": size }));
+            assert!(result.is_err());
+        }
+    }
+}
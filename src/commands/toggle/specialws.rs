@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+
+use crate::utils::hypr::{self, models::Monitor};
+use crate::utils::paths::{Paths, atomic_dump};
+
+const DEFAULT_SPECIAL: &str = "special";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Next,
+    Prev,
+}
+
+/// The last special workspace shown on each monitor, keyed by monitor name.
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct LastShown(HashMap<String, String>);
+
+impl LastShown {
+    fn load(paths: &Paths) -> Self {
+        fs::read_to_string(&paths.specialws_state_path)
+            .ok()
+            .and_then(|c| serde_json::from_str(&c).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, paths: &Paths) {
+        // Losing this only means falling back to the default next time.
+        let _ = atomic_dump(&paths.specialws_state_path, self);
+    }
+}
+
+/// The name a `togglespecialworkspace` dispatch takes for a workspace like `special:music`.
+fn special_name(workspace: &str) -> Option<&str> {
+    workspace
+        .strip_prefix("special:")
+        .filter(|name| !name.is_empty())
+}
+
+/// Picks the workspace to step to from `open` (sorted), starting at `current`.
+fn step<'a>(open: &'a [String], current: Option<&str>, direction: Direction) -> Option<&'a str> {
+    if open.is_empty() {
+        return None;
+    }
+
+    let n = open.len();
+    let index = match (
+        current.and_then(|c| open.iter().position(|o| o == c)),
+        direction,
+    ) {
+        (Some(i), Direction::Next) => (i + 1) % n,
+        (Some(i), Direction::Prev) => (i + n - 1) % n,
+        (None, Direction::Next) => 0,
+        (None, Direction::Prev) => n - 1,
+    };
+    Some(&open[index])
+}
+
+/// Toggles the special workspace of the focused monitor: hides the visible one, otherwise
+/// re-opens the one last shown there. With a direction, switches to the next or previous open
+/// special workspace instead, skipping those visible on other monitors.
+pub fn toggle(paths: &Paths, direction: Option<Direction>) -> Result<(), Box<dyn Error>> {
+    let monitors = hypr::monitors()?;
+    let Some(focused) = monitors.iter().find(|m| m.focused).or(monitors.first()) else {
+        return Err("no monitors reported by Hyprland".into());
+    };
+
+    let mut last = LastShown::load(paths);
+    let visible = special_name(&focused.special_workspace.name).map(str::to_string);
+    let remembered = last.0.get(&focused.name).cloned();
+
+    let target = match direction {
+        None => visible
+            .clone()
+            .or(remembered)
+            .unwrap_or_else(|| DEFAULT_SPECIAL.to_string()),
+        Some(direction) => {
+            let open = open_special_workspaces(&monitors, focused)?;
+            let current = visible.as_deref().or(remembered.as_deref());
+            match step(&open, current, direction) {
+                Some(target) => target.to_string(),
+                None => return Ok(()),
+            }
+        }
+    };
+
+    last.0.insert(focused.name.clone(), target.clone());
+    last.save(paths);
+
+    // Stepping onto the workspace that is already shown would hide it.
+    if direction.is_some() && visible.as_deref() == Some(target.as_str()) {
+        return Ok(());
+    }
+
+    hypr::dispatch("togglespecialworkspace", &[&target])?;
+    Ok(())
+}
+
+/// Open special workspaces, sorted, leaving out those shown on monitors other than `focused`.
+fn open_special_workspaces(
+    monitors: &[Monitor],
+    focused: &Monitor,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let elsewhere: Vec<&str> = monitors
+        .iter()
+        .filter(|m| m.name != focused.name)
+        .filter_map(|m| special_name(&m.special_workspace.name))
+        .collect();
+
+    let mut open: Vec<String> = hypr::workspaces()?
+        .iter()
+        .filter_map(|w| special_name(&w.name))
+        .filter(|name| !elsewhere.contains(name))
+        .map(str::to_string)
+        .collect();
+    open.sort();
+    open.dedup();
+    Ok(open)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(n: &[&str]) -> Vec<String> {
+        n.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_special_name() {
+        assert_eq!(special_name("special:music"), Some("music"));
+        assert_eq!(special_name("special:ü"), Some("ü"));
+        assert_eq!(special_name("special:"), None);
+        assert_eq!(special_name("3"), None);
+    }
+
+    #[test]
+    fn test_step_wraps_around() {
+        let open = names(&["music", "sysmon", "todo"]);
+
+        assert_eq!(step(&open, Some("todo"), Direction::Next), Some("music"));
+        assert_eq!(step(&open, Some("music"), Direction::Prev), Some("todo"));
+        assert_eq!(step(&open, Some("gone"), Direction::Next), Some("music"));
+        assert_eq!(step(&open, None, Direction::Prev), Some("todo"));
+        assert_eq!(step(&[], None, Direction::Next), None);
+    }
+}
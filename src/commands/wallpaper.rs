@@ -1,10 +1,12 @@
 use clap::Args;
-use std::error::Error;
 use serde_json::to_string_pretty;
+use std::error::Error;
 
-use crate::utils::paths::AppPaths;
-use crate::utils::wallpaper; // Importamos el módulo de utilidad
 use super::Runnable;
+use crate::utils::config::load_user_config;
+use crate::utils::notify::{Category, Notification, NotifyConfig, Urgency, notify};
+use crate::utils::paths::Paths;
+use crate::utils::wallpaper::{self, History};
 
 #[derive(Args, Debug)]
 pub struct WallpaperCmd {
@@ -17,45 +19,87 @@ pub struct WallpaperCmd {
     pub file: Option<String>,
 
     /// Set a random wallpaper
-    #[arg(short, long)]
+    #[arg(short, long, conflicts_with_all = ["print", "file", "history"])]
     pub random: bool,
 
     /// Disable smart color generation
     #[arg(long)]
     pub no_smart: bool,
+
+    /// Restore the previous wallpaper and its scheme
+    #[arg(long, conflicts_with_all = ["next", "random", "print", "file", "history"])]
+    pub previous: bool,
+
+    /// Restore the next wallpaper after going back
+    #[arg(long, conflicts_with_all = ["random", "print", "file", "history"])]
+    pub next: bool,
+
+    /// List the wallpaper history
+    #[arg(long, conflicts_with_all = ["print", "file"])]
+    pub history: bool,
 }
 
-impl Runnable<&AppPaths> for WallpaperCmd {
-    fn run(&self, paths: &AppPaths) -> Result<(), Box<dyn Error>> {
+/// Shows the wallpaper that was just applied, with its thumbnail when there is one.
+fn notify_changed(paths: &Paths) {
+    let Some(wall) = wallpaper::get_wallpaper(paths) else {
+        return;
+    };
+    let icon = if paths.wallpaper_thumbnail_path.exists() {
+        format!("file://{}", paths.wallpaper_thumbnail_path.display())
+    } else {
+        "preferences-desktop-wallpaper".to_string()
+    };
+
+    let user = load_user_config(paths);
+    // Best effort: the wallpaper is set without a notification server too.
+    let _ = notify(
+        &Notification::new("Wallpaper changed")
+            .category(Category::Wallpaper)
+            .urgency(Urgency::Low)
+            .body(wall.trim())
+            .icon(icon),
+        &NotifyConfig::from_user(user.as_ref(), paths),
+    );
+}
+
+impl Runnable<&Paths> for WallpaperCmd {
+    fn run(&self, paths: &Paths) -> Result<(), Box<dyn Error>> {
         match self {
-            // Caso 1: Print (--print <FILE>)
             cmd if cmd.print.is_some() => {
                 let path = cmd.print.as_ref().unwrap();
-                let colors = wallpaper::get_colours_for_wall(path, cmd.no_smart);
-                // Imprimimos el JSON formateado
+                let colors = wallpaper::get_colours_for_wall(path, cmd.no_smart, paths)?;
                 println!("{}", to_string_pretty(&colors)?);
             }
 
-            // Caso 2: Set File (--file <FILE>)
             cmd if cmd.file.is_some() => {
                 let path = cmd.file.as_ref().unwrap();
-                // Pasamos 'paths' porque set_wallpaper probablemente necesite guardar estado
                 wallpaper::set_wallpaper(path, cmd.no_smart, paths)?;
+                notify_changed(paths);
             }
 
-            // Caso 3: Random (--random)
             cmd if cmd.random => {
-                // set_random necesita 'paths' para saber dónde está la carpeta de wallpapers
-                wallpaper::set_random(paths)?;
+                wallpaper::set_random(cmd.no_smart, paths)?;
+                notify_changed(paths);
             }
 
-            // Caso 4: Default (Mostrar actual)
-            _ => {
-                match wallpaper::get_wallpaper(paths) {
-                    Some(wall) => println!("{}", wall.trim()),
-                    None => println!("No wallpaper set"),
+            cmd if cmd.previous || cmd.next => {
+                wallpaper::step_history(cmd.next, paths)?;
+                notify_changed(paths);
+            }
+
+            cmd if cmd.history => {
+                let history = History::load(paths);
+                for (i, entry) in history.entries.iter().enumerate().rev() {
+                    let marker = if i == history.cursor { "*" } else { " " };
+                    let scheme = entry.scheme["name"].as_str().unwrap_or("-");
+                    println!("{} {:>2}  {}  ({})", marker, i, entry.path, scheme);
                 }
             }
+
+            _ => match wallpaper::get_wallpaper(paths) {
+                Some(wall) => println!("{}", wall.trim()),
+                None => println!("No wallpaper set"),
+            },
         }
 
         Ok(())
@@ -4,17 +4,33 @@ mod utils;
 
 use clap::Parser;
 use std::error::Error;
+use std::process::ExitCode;
 
 use cli::{Cli, Command};
 use commands::Runnable;
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("ferret: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
     let cli = Cli::parse();
     let path = utils::paths::Paths::new();
 
     match cli.command {
         Some(Command::Shell(cmd)) => cmd.run(&path),
         Some(Command::Toggle(cmd)) => cmd.run(&path),
+        Some(Command::Wallpaper(cmd)) => cmd.run(&path),
+        Some(Command::Hypr(cmd)) => cmd.run(&path),
+        Some(Command::Screenshot(cmd)) => cmd.run(&path),
+        Some(Command::Record(cmd)) => cmd.run(&path),
+        Some(Command::Config(cmd)) => cmd.run(&path),
         Some(Command::Completions { shell }) => {
             cli::generate_completions(shell);
             Ok(())
@@ -0,0 +1,147 @@
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::paths::Paths;
+
+pub const SCHEMA: &str = include_str!("../../data/cli.schema.json");
+
+/// A problem found in a config file, located by JSON pointer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    pub file: PathBuf,
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "(root)"
+        } else {
+            &self.path
+        };
+        write!(f, "{}: {}: {}", self.file.display(), path, self.message)
+    }
+}
+
+impl ConfigIssue {
+    pub fn new(file: &Path, path: impl Into<String>, message: impl Into<String>) -> Self {
+        ConfigIssue {
+            file: file.to_path_buf(),
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn warn(&self) {
+        eprintln!("ferret: warning: {}", self);
+    }
+}
+
+fn validator() -> &'static jsonschema::Validator {
+    static VALIDATOR: OnceLock<jsonschema::Validator> = OnceLock::new();
+    VALIDATOR.get_or_init(|| {
+        let schema: Value = serde_json::from_str(SCHEMA).expect("bundled schema is valid JSON");
+        jsonschema::validator_for(&schema).expect("bundled schema is a valid JSON Schema")
+    })
+}
+
+pub fn validate_value(file: &Path, config: &Value) -> Vec<ConfigIssue> {
+    validator()
+        .iter_errors(config)
+        .map(|e| ConfigIssue::new(file, e.instance_path().to_string(), e.to_string()))
+        .collect()
+}
+
+/// Parses `content` and checks it against the schema. Invalid JSON yields a single issue
+/// and no value.
+pub fn check(file: &Path, content: &str) -> (Option<Value>, Vec<ConfigIssue>) {
+    match serde_json::from_str::<Value>(content) {
+        Ok(value) => {
+            let issues = validate_value(file, &value);
+            (Some(value), issues)
+        }
+        Err(e) => (
+            None,
+            vec![ConfigIssue::new(file, "", format!("invalid JSON: {}", e))],
+        ),
+    }
+}
+
+/// Reads the user `cli.json`, printing a warning for every problem found. Schema problems
+/// don't stop the config from loading, so the valid parts still apply.
+pub fn load_user_config(paths: &Paths) -> Option<Value> {
+    let content = match fs::read_to_string(&paths.user_config_path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            ConfigIssue::new(&paths.user_config_path, "", e.to_string()).warn();
+            return None;
+        }
+    };
+
+    let (value, issues) = check(&paths.user_config_path, &content);
+    for issue in &issues {
+        issue.warn();
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn issues(config: Value) -> Vec<ConfigIssue> {
+        validate_value(Path::new("cli.json"), &config)
+    }
+
+    #[test]
+    fn test_valid_config_has_no_issues() {
+        let config = json!({
+            "toggles": {
+                "music": {
+                    "spotify": {
+                        "enable": true,
+                        "match": [{"class": "Spotify"}],
+                        "command": ["spotify"],
+                        "move": true
+                    }
+                }
+            },
+            "scheme": { "mode": "dark", "variant": "vibrant" },
+            "theme": { "enableGtk": false }
+        });
+        assert!(issues(config).is_empty());
+    }
+
+    #[test]
+    fn test_typo_in_toggle_is_reported_with_path() {
+        let config = json!({
+            "toggles": { "music": { "spotify": { "enable": true, "comand": ["spotify"] } } }
+        });
+        let found = issues(config);
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, "/toggles/music/spotify");
+        assert!(found[0].message.contains("comand"));
+    }
+
+    #[test]
+    fn test_wrong_type_is_reported() {
+        let found = issues(json!({ "scheme": { "mode": "dim" } }));
+        assert_eq!(found[0].path, "/scheme/mode");
+    }
+
+    #[test]
+    fn test_invalid_json_is_reported_with_position() {
+        let (value, found) = check(Path::new("cli.json"), "{\n  \"toggles\": ,\n}");
+
+        assert!(value.is_none());
+        assert!(found[0].to_string().contains("line 2"));
+    }
+}
@@ -0,0 +1,318 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::io::{self, BufRead, BufReader};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+
+use super::get_instance_dir;
+
+/// An event from Hyprland's `.socket2.sock`. Window addresses are normalised to the `0x…`
+/// form used by `j/clients` so they can be compared directly.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum Event {
+    Workspace {
+        name: String,
+    },
+    WorkspaceV2 {
+        id: i64,
+        name: String,
+    },
+    FocusedMon {
+        monitor: String,
+        workspace: String,
+    },
+    ActiveWindow {
+        class: String,
+        title: String,
+    },
+    ActiveWindowV2 {
+        address: String,
+    },
+    OpenWindow {
+        address: String,
+        workspace: String,
+        class: String,
+        title: String,
+    },
+    CloseWindow {
+        address: String,
+    },
+    MoveWindow {
+        address: String,
+        workspace: String,
+    },
+    MoveWindowV2 {
+        address: String,
+        workspace_id: i64,
+        workspace: String,
+    },
+    CreateWorkspace {
+        name: String,
+    },
+    DestroyWorkspace {
+        name: String,
+    },
+    ActiveSpecial {
+        workspace: String,
+        monitor: String,
+    },
+    MonitorAdded {
+        name: String,
+    },
+    MonitorAddedV2 {
+        id: i64,
+        name: String,
+        description: String,
+    },
+    MonitorRemoved {
+        name: String,
+    },
+    Fullscreen {
+        enabled: bool,
+    },
+    Urgent {
+        address: String,
+    },
+    ConfigReloaded,
+    /// Any event not modelled above, with its raw payload.
+    Other {
+        name: String,
+        data: String,
+    },
+}
+
+fn address(raw: &str) -> String {
+    if raw.starts_with("0x") {
+        raw.to_string()
+    } else {
+        format!("0x{}", raw)
+    }
+}
+
+impl Event {
+    /// Parses one `name>>data` line. Fields are split from the left, so only the last field
+    /// (usually a window title) may itself contain commas.
+    pub fn parse(line: &str) -> Option<Self> {
+        let (name, data) = line.split_once(">>")?;
+
+        let fields = |n: usize| -> Option<Vec<String>> {
+            let parts: Vec<String> = data.splitn(n, ',').map(str::to_string).collect();
+            (parts.len() == n).then_some(parts)
+        };
+        let id = |s: &str| s.parse::<i64>().ok();
+
+        let event = match name {
+            "workspace" => Self::Workspace {
+                name: data.to_string(),
+            },
+            "workspacev2" => {
+                let f = fields(2)?;
+                Self::WorkspaceV2 {
+                    id: id(&f[0])?,
+                    name: f[1].clone(),
+                }
+            }
+            "focusedmon" => {
+                let f = fields(2)?;
+                Self::FocusedMon {
+                    monitor: f[0].clone(),
+                    workspace: f[1].clone(),
+                }
+            }
+            "activewindow" => {
+                let f = fields(2)?;
+                Self::ActiveWindow {
+                    class: f[0].clone(),
+                    title: f[1].clone(),
+                }
+            }
+            "activewindowv2" => Self::ActiveWindowV2 {
+                address: address(data),
+            },
+            "openwindow" => {
+                let f = fields(4)?;
+                Self::OpenWindow {
+                    address: address(&f[0]),
+                    workspace: f[1].clone(),
+                    class: f[2].clone(),
+                    title: f[3].clone(),
+                }
+            }
+            "closewindow" => Self::CloseWindow {
+                address: address(data),
+            },
+            "movewindow" => {
+                let f = fields(2)?;
+                Self::MoveWindow {
+                    address: address(&f[0]),
+                    workspace: f[1].clone(),
+                }
+            }
+            "movewindowv2" => {
+                let f = fields(3)?;
+                Self::MoveWindowV2 {
+                    address: address(&f[0]),
+                    workspace_id: id(&f[1])?,
+                    workspace: f[2].clone(),
+                }
+            }
+            "createworkspace" => Self::CreateWorkspace {
+                name: data.to_string(),
+            },
+            "destroyworkspace" => Self::DestroyWorkspace {
+                name: data.to_string(),
+            },
+            "activespecial" => {
+                let f = fields(2)?;
+                Self::ActiveSpecial {
+                    workspace: f[0].clone(),
+                    monitor: f[1].clone(),
+                }
+            }
+            "monitoradded" => Self::MonitorAdded {
+                name: data.to_string(),
+            },
+            "monitoraddedv2" => {
+                let f = fields(3)?;
+                Self::MonitorAddedV2 {
+                    id: id(&f[0])?,
+                    name: f[1].clone(),
+                    description: f[2].clone(),
+                }
+            }
+            "monitorremoved" => Self::MonitorRemoved {
+                name: data.to_string(),
+            },
+            "fullscreen" => Self::Fullscreen {
+                enabled: data == "1",
+            },
+            "urgent" => Self::Urgent {
+                address: address(data),
+            },
+            "configreloaded" => Self::ConfigReloaded,
+            _ => Self::Other {
+                name: name.to_string(),
+                data: data.to_string(),
+            },
+        };
+        Some(event)
+    }
+
+    /// Compact `name fields…` form for plain-text output.
+    pub fn summary(&self) -> String {
+        let value = serde_json::to_value(self).unwrap_or(Value::Null);
+        let Some(map) = value.as_object() else {
+            return String::new();
+        };
+
+        let name = map.get("event").and_then(|v| v.as_str()).unwrap_or("");
+        let fields: Vec<String> = map
+            .iter()
+            .filter(|(k, _)| k.as_str() != "event")
+            .map(|(k, v)| match v {
+                Value::String(s) => format!("{}={}", k, s),
+                other => format!("{}={}", k, other),
+            })
+            .collect();
+
+        format!("{} {}", name, fields.join(" "))
+            .trim_end()
+            .to_string()
+    }
+}
+
+fn get_event_socket_path() -> io::Result<PathBuf> {
+    Ok(get_instance_dir()?.join(".socket2.sock"))
+}
+
+/// Blocking iterator over Hyprland events. Lines that fail to parse are skipped.
+pub struct EventStream {
+    reader: BufReader<UnixStream>,
+}
+
+impl Iterator for EventStream {
+    type Item = io::Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => return None,
+                Ok(_) => {
+                    if let Some(event) = Event::parse(line.trim_end_matches('\n')) {
+                        return Some(Ok(event));
+                    }
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+pub fn events() -> io::Result<EventStream> {
+    let stream = UnixStream::connect(get_event_socket_path()?)?;
+    Ok(EventStream {
+        reader: BufReader::new(stream),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_openwindow_keeps_commas_in_title() {
+        let event = Event::parse("openwindow>>80e62df0,2,discord,#general, hello - Discord");
+        assert_eq!(
+            event,
+            Some(Event::OpenWindow {
+                address: "0x80e62df0".to_string(),
+                workspace: "2".to_string(),
+                class: "discord".to_string(),
+                title: "#general, hello - Discord".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_v2_events() {
+        assert_eq!(
+            Event::parse("workspacev2>>3,code"),
+            Some(Event::WorkspaceV2 {
+                id: 3,
+                name: "code".to_string()
+            })
+        );
+        assert_eq!(
+            Event::parse("activespecial>>special:music,DP-1"),
+            Some(Event::ActiveSpecial {
+                workspace: "special:music".to_string(),
+                monitor: "DP-1".to_string()
+            })
+        );
+        assert_eq!(Event::parse("workspacev2>>x,code"), None);
+    }
+
+    #[test]
+    fn test_parse_unknown_and_malformed() {
+        assert_eq!(
+            Event::parse("screencast>>1,0"),
+            Some(Event::Other {
+                name: "screencast".to_string(),
+                data: "1,0".to_string()
+            })
+        );
+        assert_eq!(Event::parse("garbage"), None);
+    }
+
+    #[test]
+    fn test_event_json_uses_hyprland_names() {
+        let event = Event::parse("monitoradded>>HDMI-A-1").unwrap();
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["event"], "monitoradded");
+        assert_eq!(json["name"], "HDMI-A-1");
+        assert_eq!(event.summary(), "monitoradded name=HDMI-A-1");
+    }
+}
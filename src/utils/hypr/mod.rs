@@ -0,0 +1,357 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+pub mod events;
+pub mod models;
+
+use models::{Client, Devices, Layers, Monitor, Workspace};
+
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(2);
+const RELOAD_RETRIES: u32 = 3;
+const RELOAD_RETRY_DELAY: Duration = Duration::from_millis(200);
+/// Separator Hyprland puts between the replies of a `[[BATCH]]` request.
+const BATCH_DELIMITER: &str = "\n\n\n";
+
+#[derive(Debug)]
+pub enum HyprError {
+    Io(io::Error),
+    /// Hyprland answered a command with something other than `ok`.
+    Rejected {
+        command: String,
+        reason: String,
+    },
+    /// A batch reply that can't be matched up with the commands that were sent.
+    Batch {
+        commands: usize,
+        reply: String,
+    },
+}
+
+impl fmt::Display for HyprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HyprError::Io(e) => write!(f, "Hyprland IPC failed: {}", e),
+            HyprError::Rejected { command, reason } => {
+                write!(f, "Hyprland rejected `{}`: {}", command, reason)
+            }
+            HyprError::Batch { commands, reply } => {
+                write!(
+                    f,
+                    "Hyprland rejected a batch of {} commands: {}",
+                    commands, reply
+                )
+            }
+        }
+    }
+}
+
+impl Error for HyprError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HyprError::Io(e) => Some(e),
+            HyprError::Rejected { .. } | HyprError::Batch { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for HyprError {
+    fn from(e: io::Error) -> Self {
+        HyprError::Io(e)
+    }
+}
+
+/// One sub-command of a batch request and the reply Hyprland gave for it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchReply {
+    pub command: String,
+    pub response: String,
+}
+
+impl BatchReply {
+    pub fn check(&self) -> Result<(), HyprError> {
+        check_reply(&self.command, &self.response)
+    }
+}
+
+fn get_instance_dir() -> io::Result<PathBuf> {
+    let runtime_dir = env::var("XDG_RUNTIME_DIR").map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("XDG_RUNTIME_DIR not set: {}", e),
+        )
+    })?;
+
+    let signature = env::var("HYPRLAND_INSTANCE_SIGNATURE").map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("HYPRLAND_INSTANCE_SIGNATURE not set: {}", e),
+        )
+    })?;
+
+    Ok(PathBuf::from(runtime_dir).join("hypr").join(signature))
+}
+
+fn get_socket_path() -> io::Result<PathBuf> {
+    Ok(get_instance_dir()?.join(".socket.sock"))
+}
+
+fn request_once(payload: &str) -> io::Result<String> {
+    let socket_path = get_socket_path()?;
+
+    let mut stream = UnixStream::connect(socket_path)?;
+    stream.set_read_timeout(Some(SOCKET_TIMEOUT))?;
+    stream.set_write_timeout(Some(SOCKET_TIMEOUT))?;
+
+    stream.write_all(payload.as_bytes())?;
+
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("timed out waiting for a reply to `{}`", payload),
+            ),
+            _ => e,
+        })?;
+
+    Ok(response)
+}
+
+/// Sends `payload` to the request socket. While Hyprland reloads the socket briefly refuses
+/// connections or closes without replying, so those cases are retried a few times. An empty
+/// reply is only retried for `j/` queries: a dispatch may have run before the socket closed,
+/// and sending it again could start an app twice.
+fn request(payload: &str) -> io::Result<String> {
+    let read_only = payload.starts_with("j/");
+    let mut attempt = 0;
+    loop {
+        let result = request_once(payload);
+
+        let reloading = match &result {
+            Ok(response) => read_only && response.is_empty(),
+            Err(e) => matches!(
+                e.kind(),
+                io::ErrorKind::ConnectionRefused | io::ErrorKind::NotFound
+            ),
+        };
+
+        if !reloading || attempt >= RELOAD_RETRIES {
+            return result;
+        }
+        attempt += 1;
+        thread::sleep(RELOAD_RETRY_DELAY * attempt);
+    }
+}
+
+fn check_reply(command: &str, response: &str) -> Result<(), HyprError> {
+    let response = response.trim();
+    if response == "ok" {
+        Ok(())
+    } else {
+        Err(HyprError::Rejected {
+            command: command.to_string(),
+            reason: if response.is_empty() {
+                "no reply".to_string()
+            } else {
+                response.to_string()
+            },
+        })
+    }
+}
+
+/// Sends `j/<msg>` and deserializes the reply into `T`, naming the request and the offending
+/// field when Hyprland's output no longer matches the model.
+fn query<T: DeserializeOwned>(msg: &str) -> io::Result<T> {
+    let response = request(&format!("j/{}", msg))?;
+
+    serde_json::from_str(&response).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unexpected reply to hyprctl {}: {}", msg, e),
+        )
+    })
+}
+
+pub fn clients() -> io::Result<Vec<Client>> {
+    query("clients")
+}
+
+pub fn monitors() -> io::Result<Vec<Monitor>> {
+    query("monitors")
+}
+
+pub fn workspaces() -> io::Result<Vec<Workspace>> {
+    query("workspaces")
+}
+
+/// The focused window, or `None` when nothing is focused (Hyprland replies with `{}`).
+pub fn active_window() -> io::Result<Option<Client>> {
+    let value: Value = query("activewindow")?;
+    if value.as_object().is_some_and(|o| o.is_empty()) {
+        return Ok(None);
+    }
+
+    serde_json::from_value(value).map(Some).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unexpected reply to hyprctl activewindow: {}", e),
+        )
+    })
+}
+
+pub fn devices() -> io::Result<Devices> {
+    query("devices")
+}
+
+pub fn layers() -> io::Result<Layers> {
+    query("layers")
+}
+
+fn dispatch_command(dispatcher: &str, args: &[&str]) -> String {
+    let args_str = args.join(" ");
+    format!("dispatch {} {}", dispatcher, args_str)
+        .trim_end()
+        .to_string()
+}
+
+pub fn dispatch(dispatcher: &str, args: &[&str]) -> Result<(), HyprError> {
+    let cmd = dispatch_command(dispatcher, args);
+    let resp = request(&cmd)?;
+
+    check_reply(&cmd, &resp)
+}
+
+/// Pairs each sub-command with its slice of a batch reply. Older Hyprland versions
+/// concatenate replies without a delimiter, which is only recoverable when all are `ok`;
+/// any other mismatch is reported for the batch as a whole.
+fn split_batch_reply(commands: &[String], response: &str) -> Result<Vec<BatchReply>, HyprError> {
+    let mut parts: Vec<String> = response
+        .split(BATCH_DELIMITER)
+        .map(str::to_string)
+        .collect();
+
+    if parts.len() != commands.len() {
+        if response != "ok".repeat(commands.len()) {
+            return Err(HyprError::Batch {
+                commands: commands.len(),
+                reply: if response.trim().is_empty() {
+                    "no reply".to_string()
+                } else {
+                    response.trim().to_string()
+                },
+            });
+        }
+        parts = vec!["ok".to_string(); commands.len()];
+    }
+
+    Ok(commands
+        .iter()
+        .zip(parts)
+        .map(|(command, response)| BatchReply {
+            command: command.clone(),
+            response,
+        })
+        .collect())
+}
+
+fn send_batch(commands: &[String]) -> Result<Vec<BatchReply>, HyprError> {
+    if commands.is_empty() {
+        return Ok(vec![]);
+    }
+    let response = request(&format!("[[BATCH]]{}", commands.join(";")))?;
+    split_batch_reply(commands, &response)
+}
+
+/// Sends several commands in as few requests as possible and returns their replies in
+/// order. Hyprland splits batches on every `;`, so commands containing one (such as `exec`
+/// with several window rules or a shell snippet) are sent on their own between batches.
+pub fn batch(msgs: &[&str], json_inner: bool) -> Result<Vec<BatchReply>, HyprError> {
+    let processed_msgs: Vec<String> = if json_inner {
+        msgs.iter().map(|m| format!("j/{}", m.trim())).collect()
+    } else {
+        msgs.iter().map(|m| m.trim().to_string()).collect()
+    };
+
+    let mut replies = Vec::with_capacity(processed_msgs.len());
+    let mut run: Vec<String> = Vec::new();
+    for msg in processed_msgs {
+        if msg.contains(';') {
+            replies.extend(send_batch(&run)?);
+            run.clear();
+            let response = request(&msg)?;
+            replies.push(BatchReply {
+                command: msg,
+                response,
+            });
+        } else {
+            run.push(msg);
+        }
+    }
+    replies.extend(send_batch(&run)?);
+    Ok(replies)
+}
+
+/// Runs several `(dispatcher, args)` pairs through [`batch`], failing with the first one
+/// Hyprland rejected.
+pub fn batch_dispatch<S: AsRef<str>>(dispatches: &[(&str, S)]) -> Result<(), HyprError> {
+    let commands: Vec<String> = dispatches
+        .iter()
+        .map(|(dispatcher, args)| dispatch_command(dispatcher, &[args.as_ref()]))
+        .collect();
+    let commands: Vec<&str> = commands.iter().map(String::as_str).collect();
+
+    for reply in batch(&commands, false)? {
+        reply.check()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmds(c: &[&str]) -> Vec<String> {
+        c.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_split_batch_reply_maps_each_command() {
+        let commands = cmds(&["dispatch exec foot", "dispatch bogus"]);
+        let replies = split_batch_reply(&commands, "ok\n\n\nInvalid dispatcher").unwrap();
+
+        assert!(replies[0].check().is_ok());
+        let err = replies[1].check().unwrap_err().to_string();
+        assert!(err.contains("dispatch bogus"));
+        assert!(err.contains("Invalid dispatcher"));
+    }
+
+    #[test]
+    fn test_split_batch_reply_legacy_concatenated_ok() {
+        let commands = cmds(&["dispatch a", "dispatch b", "dispatch c"]);
+        let replies = split_batch_reply(&commands, "okokok").unwrap();
+        assert!(replies.iter().all(|r| r.check().is_ok()));
+    }
+
+    #[test]
+    fn test_split_batch_reply_mismatch_is_one_error() {
+        let commands = cmds(&["dispatch a", "dispatch b"]);
+        let err = split_batch_reply(&commands, "okInvalid dispatcher").unwrap_err();
+        assert!(matches!(err, HyprError::Batch { commands: 2, .. }));
+        assert!(err.to_string().contains("batch of 2 commands"));
+    }
+
+    #[test]
+    fn test_check_reply_reports_empty_reply() {
+        let err = check_reply("dispatch exec x", "").unwrap_err();
+        assert!(err.to_string().contains("no reply"));
+    }
+}
@@ -0,0 +1,290 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Older Hyprland versions report `fullscreen` as a bool, newer ones as a mode number.
+fn bool_or_int<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum BoolOrInt {
+        Bool(bool),
+        Int(u8),
+    }
+
+    Ok(match BoolOrInt::deserialize(deserializer)? {
+        BoolOrInt::Bool(b) => b as u8,
+        BoolOrInt::Int(i) => i,
+    })
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WorkspaceRef {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Client {
+    pub address: String,
+    #[serde(default = "default_true")]
+    pub mapped: bool,
+    #[serde(default)]
+    pub hidden: bool,
+    pub at: [i32; 2],
+    pub size: [i32; 2],
+    pub workspace: WorkspaceRef,
+    pub floating: bool,
+    pub monitor: i64,
+    pub class: String,
+    pub title: String,
+    pub initial_class: String,
+    pub initial_title: String,
+    pub pid: i64,
+    pub xwayland: bool,
+    pub pinned: bool,
+    #[serde(default, deserialize_with = "bool_or_int")]
+    pub fullscreen: u8,
+    #[serde(default, rename = "focusHistoryID")]
+    pub focus_history_id: i64,
+    /// Fields not modelled above, kept so match rules can still reach them.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Client {
+    /// The client as Hyprland reported it, for matching against user-written JSON rules.
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Monitor {
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub width: i32,
+    pub height: i32,
+    pub refresh_rate: f64,
+    pub x: i32,
+    pub y: i32,
+    pub active_workspace: WorkspaceRef,
+    pub special_workspace: WorkspaceRef,
+    #[serde(default)]
+    pub reserved: [i32; 4],
+    pub scale: f64,
+    #[serde(default)]
+    pub transform: i32,
+    pub focused: bool,
+    #[serde(default)]
+    pub disabled: bool,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Workspace {
+    pub id: i64,
+    pub name: String,
+    pub monitor: String,
+    #[serde(rename = "monitorID", default)]
+    pub monitor_id: i64,
+    pub windows: u32,
+    #[serde(rename = "hasfullscreen")]
+    pub has_fullscreen: bool,
+    #[serde(rename = "lastwindow")]
+    pub last_window: String,
+    #[serde(rename = "lastwindowtitle")]
+    pub last_window_title: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Mouse {
+    pub address: String,
+    pub name: String,
+    #[serde(default)]
+    pub default_speed: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Keyboard {
+    pub address: String,
+    pub name: String,
+    #[serde(default)]
+    pub rules: String,
+    #[serde(default)]
+    pub model: String,
+    #[serde(default)]
+    pub layout: String,
+    #[serde(default)]
+    pub variant: String,
+    #[serde(default)]
+    pub options: String,
+    pub active_keymap: String,
+    #[serde(default)]
+    pub main: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Device {
+    pub address: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Devices {
+    pub mice: Vec<Mouse>,
+    pub keyboards: Vec<Keyboard>,
+    #[serde(default)]
+    pub tablets: Vec<Device>,
+    #[serde(default)]
+    pub touch: Vec<Device>,
+    #[serde(default)]
+    pub switches: Vec<Device>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Layer {
+    pub address: String,
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+    pub namespace: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct MonitorLayers {
+    /// Layer surfaces keyed by layer level ("0" background to "3" overlay).
+    pub levels: HashMap<String, Vec<Layer>>,
+}
+
+/// `j/layers` output, keyed by monitor name.
+pub type Layers = HashMap<String, MonitorLayers>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLIENT: &str = r#"{
+        "address": "0x55d1c0a0", "mapped": true, "hidden": false,
+        "at": [10, 20], "size": [800, 600],
+        "workspace": {"id": -98, "name": "special:communication"},
+        "floating": false, "pseudo": false, "monitor": 0,
+        "class": "discord", "title": "Discord", "initialClass": "discord",
+        "initialTitle": "Discord", "pid": 4242, "xwayland": false, "pinned": false,
+        "fullscreen": 0, "fullscreenClient": 0, "grouped": [], "tags": [],
+        "swallowing": "0x0", "focusHistoryID": 3
+    }"#;
+
+    #[test]
+    fn test_client_parses_and_keeps_extra_fields() {
+        let client: Client = serde_json::from_str(CLIENT).unwrap();
+
+        assert_eq!(client.workspace.name, "special:communication");
+        assert_eq!(client.initial_class, "discord");
+        assert_eq!(client.focus_history_id, 3);
+
+        let value = client.to_value();
+        assert_eq!(value["initialTitle"], "Discord");
+        assert_eq!(value["swallowing"], "0x0");
+    }
+
+    #[test]
+    fn test_client_accepts_legacy_bool_fullscreen() {
+        let json = CLIENT.replace(r#""fullscreen": 0"#, r#""fullscreen": true"#);
+        let client: Client = serde_json::from_str(&json).unwrap();
+        assert_eq!(client.fullscreen, 1);
+    }
+
+    #[test]
+    fn test_client_missing_field_names_it() {
+        let json = CLIENT.replace(r#""class": "discord","#, "");
+        let err = serde_json::from_str::<Client>(&json).unwrap_err();
+        assert!(err.to_string().contains("`class`"));
+    }
+
+    /// `hyprctl -j devices` from Hyprland 0.45.
+    const DEVICES: &str = r#"{
+        "mice": [
+            {"address": "0x5611a2b3c4d0", "name": "logitech-g502-hero", "defaultSpeed": 0.00000, "scrollFactor": 1.00}
+        ],
+        "keyboards": [
+            {
+                "address": "0x5611a2b3d120", "name": "at-translated-set-2-keyboard",
+                "rules": "", "model": "", "layout": "us,de", "variant": "", "options": "grp:alt_shift_toggle",
+                "active_layout_index": 0, "active_keymap": "English (US)",
+                "capsLock": false, "numLock": true, "main": true
+            },
+            {
+                "address": "0x5611a2b3e870", "name": "power-button",
+                "rules": "", "model": "", "layout": "us", "variant": "", "options": "",
+                "active_layout_index": 0, "active_keymap": "English (US)",
+                "capsLock": false, "numLock": false, "main": false
+            }
+        ],
+        "tablets": [],
+        "touch": [],
+        "switches": [
+            {"address": "0x5611a2b3f010", "name": "Lid Switch"}
+        ]
+    }"#;
+
+    /// `hyprctl -j layers` from Hyprland 0.45.
+    const LAYERS: &str = r#"{
+        "DP-1": {
+            "levels": {
+                "0": [
+                    {"address": "0x5611a2c01230", "x": 0, "y": 0, "w": 2560, "h": 1440, "namespace": "hyprpaper", "pid": 1403}
+                ],
+                "1": [],
+                "2": [
+                    {"address": "0x5611a2c04560", "x": 0, "y": 0, "w": 2560, "h": 40, "namespace": "ferret-bar", "pid": 1520}
+                ],
+                "3": []
+            }
+        },
+        "HDMI-A-1": {
+            "levels": {"0": [], "1": [], "2": [], "3": []}
+        }
+    }"#;
+
+    #[test]
+    fn test_devices_parse() {
+        let devices: Devices = serde_json::from_str(DEVICES).unwrap();
+
+        assert_eq!(devices.mice[0].name, "logitech-g502-hero");
+        assert_eq!(devices.keyboards.len(), 2);
+        let main = devices.keyboards.iter().find(|k| k.main).unwrap();
+        assert_eq!(main.layout, "us,de");
+        assert_eq!(main.active_keymap, "English (US)");
+        assert!(devices.tablets.is_empty());
+        assert_eq!(devices.switches[0].name, "Lid Switch");
+    }
+
+    #[test]
+    fn test_devices_without_optional_lists() {
+        let devices: Devices = serde_json::from_str(r#"{"mice": [], "keyboards": []}"#).unwrap();
+        assert!(devices.touch.is_empty() && devices.switches.is_empty());
+    }
+
+    #[test]
+    fn test_layers_parse() {
+        let layers: Layers = serde_json::from_str(LAYERS).unwrap();
+
+        let bar = &layers["DP-1"].levels["2"][0];
+        assert_eq!(bar.namespace, "ferret-bar");
+        assert_eq!((bar.w, bar.h), (2560, 40));
+        assert_eq!(layers["DP-1"].levels["0"][0].namespace, "hyprpaper");
+        assert!(layers["HDMI-A-1"].levels.values().all(Vec::is_empty));
+    }
+}
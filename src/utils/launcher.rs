@@ -0,0 +1,325 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::config::ConfigIssue;
+use super::paths::Paths;
+
+/// How toggled apps are started from a Hyprland `exec` dispatch.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum Strategy {
+    /// The first of app2unit, uwsm and systemd-run that is installed, else plain exec.
+    #[default]
+    Auto,
+    App2unit,
+    Uwsm,
+    SystemdRun,
+    Exec,
+    /// The `template` from the launcher config.
+    Custom,
+}
+
+impl Strategy {
+    fn binary(&self) -> Option<&'static str> {
+        match self {
+            Strategy::App2unit => Some("app2unit"),
+            Strategy::Uwsm => Some("uwsm"),
+            Strategy::SystemdRun => Some("systemd-run"),
+            _ => None,
+        }
+    }
+
+    /// Whether the launcher understands desktop entry IDs itself.
+    fn handles_desktop_ids(&self) -> bool {
+        matches!(self, Strategy::App2unit | Strategy::Uwsm)
+    }
+}
+
+/// The `launcher` section of `cli.json`.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct LauncherConfig {
+    #[serde(default)]
+    pub strategy: Strategy,
+    /// Command template for the custom strategy; `{cmd}` is replaced by the quoted command.
+    pub template: Option<String>,
+}
+
+impl LauncherConfig {
+    /// Reads the `launcher` section of the user config, warning about and ignoring a
+    /// malformed one.
+    pub fn from_user(user: Option<&Value>, paths: &Paths) -> Self {
+        let Some(section) = user.and_then(|v| v.get("launcher")) else {
+            return LauncherConfig::default();
+        };
+        serde_json::from_value(section.clone()).unwrap_or_else(|e| {
+            ConfigIssue::new(&paths.user_config_path, "/launcher", e.to_string()).warn();
+            LauncherConfig::default()
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum LauncherError {
+    /// The custom strategy was picked without a `template`.
+    MissingTemplate,
+    /// The launcher binary for an explicitly picked strategy isn't on `PATH`.
+    NotInstalled(&'static str),
+    /// A desktop entry ID that isn't in any of the application dirs.
+    EntryNotFound(String),
+    /// A desktop entry without an `Exec=` line that can be run.
+    NoExec(PathBuf),
+}
+
+impl fmt::Display for LauncherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LauncherError::MissingTemplate => {
+                write!(f, "launcher strategy \"custom\" needs a template")
+            }
+            LauncherError::NotInstalled(bin) => write!(f, "launcher {} is not installed", bin),
+            LauncherError::EntryNotFound(id) => {
+                write!(f, "{} not found in application dirs", id)
+            }
+            LauncherError::NoExec(path) => {
+                write!(f, "{} has no usable Exec line", path.display())
+            }
+        }
+    }
+}
+
+impl Error for LauncherError {}
+
+#[derive(Debug, Clone)]
+pub struct Launcher {
+    strategy: Strategy,
+    template: Option<String>,
+}
+
+impl Launcher {
+    pub fn new(config: &LauncherConfig) -> Result<Self, LauncherError> {
+        let strategy = match config.strategy {
+            Strategy::Auto => [Strategy::App2unit, Strategy::Uwsm, Strategy::SystemdRun]
+                .into_iter()
+                .find(|s| s.binary().is_some_and(|b| which::which(b).is_ok()))
+                .unwrap_or(Strategy::Exec),
+            Strategy::Custom if config.template.is_none() => {
+                return Err(LauncherError::MissingTemplate);
+            }
+            explicit => {
+                if let Some(bin) = explicit.binary()
+                    && which::which(bin).is_err()
+                {
+                    return Err(LauncherError::NotInstalled(bin));
+                }
+                explicit
+            }
+        };
+
+        Ok(Launcher {
+            strategy,
+            template: config.template.clone(),
+        })
+    }
+
+    /// Builds the shell command Hyprland should exec for `cmd`. Desktop entries are passed
+    /// through to launchers that support them and expanded from their `Exec=` line otherwise.
+    pub fn command_line(&self, cmd: &[String]) -> Result<String, LauncherError> {
+        let expanded;
+        let cmd = match cmd.first() {
+            Some(first) if is_desktop_id(first) && !self.strategy.handles_desktop_ids() => {
+                let entry = find_desktop_entry(first, &application_dirs())
+                    .ok_or_else(|| LauncherError::EntryNotFound(first.clone()))?;
+                expanded =
+                    desktop_exec(&entry).ok_or_else(|| LauncherError::NoExec(entry.clone()))?;
+                &expanded
+            }
+            _ => cmd,
+        };
+
+        let joined = shell_words::join(cmd);
+        Ok(match self.strategy {
+            Strategy::App2unit => format!("app2unit -- {}", joined),
+            Strategy::Uwsm => format!("uwsm app -- {}", joined),
+            Strategy::SystemdRun => format!("systemd-run --user --scope -- {}", joined),
+            Strategy::Custom => self
+                .template
+                .as_deref()
+                .unwrap_or("{cmd}")
+                .replace("{cmd}", &joined),
+            Strategy::Exec | Strategy::Auto => joined,
+        })
+    }
+}
+
+pub fn is_desktop_id(cmd: &str) -> bool {
+    cmd.ends_with(".desktop")
+}
+
+/// `$XDG_DATA_HOME/applications` followed by `applications` in each of `$XDG_DATA_DIRS`.
+pub fn application_dirs() -> Vec<PathBuf> {
+    let home = env::var("HOME").map(PathBuf::from).unwrap_or_default();
+    let data_home = env::var("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| home.join(".local/share"));
+    let data_dirs =
+        env::var("XDG_DATA_DIRS").unwrap_or_else(|_| "/usr/local/share:/usr/share".to_string());
+
+    std::iter::once(data_home)
+        .chain(
+            data_dirs
+                .split(':')
+                .filter(|d| !d.is_empty())
+                .map(PathBuf::from),
+        )
+        .map(|d| d.join("applications"))
+        .collect()
+}
+
+/// Resolves a desktop file ID, including the `vendor-app.desktop` form that refers to
+/// `vendor/app.desktop`.
+pub fn find_desktop_entry(id: &str, dirs: &[PathBuf]) -> Option<PathBuf> {
+    let mut candidates = vec![PathBuf::from(id)];
+    let mut rest = id;
+    let mut prefix = PathBuf::new();
+    while let Some((dir, tail)) = rest.split_once('-') {
+        prefix.push(dir);
+        candidates.push(prefix.join(tail));
+        rest = tail;
+    }
+
+    dirs.iter()
+        .flat_map(|dir| candidates.iter().map(move |c| dir.join(c)))
+        .find(|p| p.is_file())
+}
+
+/// The `Exec=` command of a desktop entry with field codes like `%U` removed.
+pub fn desktop_exec(path: &Path) -> Option<Vec<String>> {
+    let content = fs::read_to_string(path).ok()?;
+
+    let mut in_main_group = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_main_group = line == "[Desktop Entry]";
+            continue;
+        }
+        if in_main_group && let Some(exec) = line.strip_prefix("Exec=") {
+            let args: Vec<String> = shell_words::split(exec)
+                .ok()?
+                .into_iter()
+                .filter(|a| !(a.len() == 2 && a.starts_with('%')))
+                .map(|a| a.replace("%%", "%"))
+                .collect();
+            return (!args.is_empty()).then_some(args);
+        }
+    }
+    None
+}
+
+/// Whether `cmd` can be launched: an installed desktop entry or an executable on `PATH`.
+pub fn command_exists(cmd: &str) -> bool {
+    if is_desktop_id(cmd) {
+        return find_desktop_entry(cmd, &application_dirs()).is_some();
+    }
+    which::which(cmd).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn launcher(strategy: Strategy, template: Option<&str>) -> Launcher {
+        Launcher {
+            strategy,
+            template: template.map(str::to_string),
+        }
+    }
+
+    fn cmd(parts: &[&str]) -> Vec<String> {
+        parts.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_command_line_per_strategy() {
+        let c = cmd(&["foot", "-T", "my term"]);
+
+        assert_eq!(
+            launcher(Strategy::App2unit, None).command_line(&c).unwrap(),
+            "app2unit -- foot -T 'my term'"
+        );
+        assert_eq!(
+            launcher(Strategy::Uwsm, None).command_line(&c).unwrap(),
+            "uwsm app -- foot -T 'my term'"
+        );
+        assert_eq!(
+            launcher(Strategy::SystemdRun, None)
+                .command_line(&c)
+                .unwrap(),
+            "systemd-run --user --scope -- foot -T 'my term'"
+        );
+        assert_eq!(
+            launcher(Strategy::Exec, None).command_line(&c).unwrap(),
+            "foot -T 'my term'"
+        );
+        assert_eq!(
+            launcher(Strategy::Custom, Some("runapp {cmd} &"))
+                .command_line(&c)
+                .unwrap(),
+            "runapp foot -T 'my term' &"
+        );
+    }
+
+    #[test]
+    fn test_custom_strategy_requires_template() {
+        let config = LauncherConfig {
+            strategy: Strategy::Custom,
+            template: None,
+        };
+        assert!(matches!(
+            Launcher::new(&config),
+            Err(LauncherError::MissingTemplate)
+        ));
+    }
+
+    #[test]
+    fn test_find_desktop_entry_with_vendor_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let apps = dir.path().join("applications");
+        fs::create_dir_all(apps.join("kde")).unwrap();
+        fs::write(apps.join("kde/konsole.desktop"), "").unwrap();
+        fs::write(apps.join("foot.desktop"), "").unwrap();
+
+        let dirs = vec![PathBuf::from("/nonexistent"), apps.clone()];
+        assert_eq!(
+            find_desktop_entry("foot.desktop", &dirs),
+            Some(apps.join("foot.desktop"))
+        );
+        assert_eq!(
+            find_desktop_entry("kde-konsole.desktop", &dirs),
+            Some(apps.join("kde/konsole.desktop"))
+        );
+        assert_eq!(find_desktop_entry("missing.desktop", &dirs), None);
+    }
+
+    #[test]
+    fn test_desktop_exec_strips_field_codes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("discord.desktop");
+        fs::write(
+            &path,
+            "[Desktop Entry]\nName=Discord\nExec=/opt/discord/Discord --flag %U\n\n\
+             [Desktop Action new]\nExec=/opt/discord/Discord --new\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            desktop_exec(&path),
+            Some(cmd(&["/opt/discord/Discord", "--flag"]))
+        );
+    }
+}
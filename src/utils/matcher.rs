@@ -0,0 +1,335 @@
+//! Toggle match rules, compiled once when the config is loaded. Plain values match as
+//! before; objects whose keys are all operators (`regex`, `not`, `any`, `all`, `eq`, `gt`,
+//! `gte`, `lt`, `lte`) are evaluated instead of compared.
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use std::error::Error;
+use std::fmt;
+
+const OPERATORS: &[&str] = &["regex", "not", "any", "all", "eq", "gt", "gte", "lt", "lte"];
+
+/// A rule that can't be compiled, with the path of the offending part relative to the rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleError {
+    pub path: String,
+    pub problem: String,
+}
+
+impl RuleError {
+    fn new(path: &str, problem: impl Into<String>) -> Self {
+        RuleError {
+            path: path.to_string(),
+            problem: problem.into(),
+        }
+    }
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.problem)
+        } else {
+            write!(f, "{}: {}", self.path, self.problem)
+        }
+    }
+}
+
+impl Error for RuleError {}
+
+#[derive(Debug, Clone, Copy)]
+enum Cmp {
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+impl Cmp {
+    fn holds(self, v: f64, a: f64) -> bool {
+        match self {
+            Cmp::Gt => v > a,
+            Cmp::Gte => v >= a,
+            Cmp::Lt => v < a,
+            Cmp::Lte => v <= a,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Op {
+    Regex(Regex),
+    Not(Box<Node>),
+    Any(Vec<Node>),
+    All(Vec<Node>),
+    Eq(Value),
+    Compare(Cmp, Value),
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    /// Every operator must hold for the value.
+    Ops(Vec<Op>),
+    /// Each field (or array position) must match its rule.
+    Fields(Vec<(String, Node)>),
+    /// Substring for strings, containment for arrays, equality otherwise.
+    Plain(Value),
+}
+
+fn is_operator(map: &Map<String, Value>) -> bool {
+    !map.is_empty() && map.keys().all(|k| OPERATORS.contains(&k.as_str()))
+}
+
+fn is_number_or_numbers(v: &Value) -> bool {
+    v.is_number() || v.as_array().is_some_and(|a| a.iter().all(Value::is_number))
+}
+
+fn compile_op(op: &str, arg: &Value, path: &str) -> Result<Op, RuleError> {
+    let compile_list = |arg: &Value| -> Result<Vec<Node>, RuleError> {
+        let rules = arg
+            .as_array()
+            .ok_or_else(|| RuleError::new(path, format!("{} takes an array of rules", op)))?;
+        rules
+            .iter()
+            .enumerate()
+            .map(|(i, r)| compile(r, &format!("{}/{}", path, i)))
+            .collect()
+    };
+
+    Ok(match op {
+        "regex" => {
+            let pattern = arg
+                .as_str()
+                .ok_or_else(|| RuleError::new(path, "regex must be a string"))?;
+            let re = Regex::new(pattern)
+                .map_err(|e| RuleError::new(path, format!("invalid regex: {}", e)))?;
+            Op::Regex(re)
+        }
+        "not" => Op::Not(Box::new(compile(arg, path)?)),
+        "any" => Op::Any(compile_list(arg)?),
+        "all" => Op::All(compile_list(arg)?),
+        "eq" => Op::Eq(arg.clone()),
+        _ => {
+            let cmp = match op {
+                "gt" => Cmp::Gt,
+                "gte" => Cmp::Gte,
+                "lt" => Cmp::Lt,
+                _ => Cmp::Lte,
+            };
+            if !is_number_or_numbers(arg) {
+                return Err(RuleError::new(
+                    path,
+                    format!("{} takes a number or array of numbers", op),
+                ));
+            }
+            Op::Compare(cmp, arg.clone())
+        }
+    })
+}
+
+fn compile(rule: &Value, path: &str) -> Result<Node, RuleError> {
+    match rule {
+        Value::Object(map) if is_operator(map) => map
+            .iter()
+            .map(|(op, arg)| compile_op(op, arg, &format!("{}/{}", path, op)))
+            .collect::<Result<_, _>>()
+            .map(Node::Ops),
+        Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), compile(v, &format!("{}/{}", path, k))?)))
+            .collect::<Result<_, _>>()
+            .map(Node::Fields),
+        other => Ok(Node::Plain(other.clone())),
+    }
+}
+
+fn compare(value: &Value, arg: &Value, cmp: Cmp) -> bool {
+    match (value, arg) {
+        (Value::Array(values), Value::Array(args)) => {
+            values.len() == args.len() && values.iter().zip(args).all(|(v, a)| compare(v, a, cmp))
+        }
+        _ => match (value.as_f64(), arg.as_f64()) {
+            (Some(v), Some(a)) => cmp.holds(v, a),
+            _ => false,
+        },
+    }
+}
+
+impl Op {
+    fn matches(&self, value: &Value) -> bool {
+        match self {
+            Op::Regex(re) => match value {
+                Value::String(s) => re.is_match(s),
+                Value::Number(n) => re.is_match(&n.to_string()),
+                _ => false,
+            },
+            Op::Not(rule) => !rule.matches(value),
+            Op::Any(rules) => rules.iter().any(|r| r.matches(value)),
+            Op::All(rules) => rules.iter().all(|r| r.matches(value)),
+            Op::Eq(arg) => value == arg,
+            Op::Compare(cmp, arg) => compare(value, arg, *cmp),
+        }
+    }
+}
+
+impl Node {
+    fn matches(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Node::Ops(ops), _) => ops.iter().all(|op| op.matches(value)),
+            (Node::Fields(fields), Value::Object(map)) => fields
+                .iter()
+                .all(|(k, rule)| map.get(k).is_some_and(|v| rule.matches(v))),
+            (Node::Fields(fields), Value::Array(arr)) => fields.iter().all(|(k, rule)| {
+                k.parse::<usize>()
+                    .ok()
+                    .and_then(|i| arr.get(i))
+                    .is_some_and(|v| rule.matches(v))
+            }),
+            (Node::Fields(_), _) => false,
+            (Node::Plain(Value::String(sub)), Value::String(s)) => s.contains(sub.as_str()),
+            (Node::Plain(Value::Array(sub)), Value::Array(arr)) => {
+                sub.iter().all(|item| arr.contains(item))
+            }
+            (Node::Plain(rule), value) => value == rule,
+        }
+    }
+}
+
+/// A compiled match rule, keeping the JSON it came from for display.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    source: Value,
+    node: Node,
+}
+
+impl Rule {
+    pub fn compile(source: &Value) -> Result<Self, RuleError> {
+        Ok(Rule {
+            source: source.clone(),
+            node: compile(source, "")?,
+        })
+    }
+
+    pub fn matches(&self, value: &Value) -> bool {
+        self.node.matches(value)
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let source = Value::deserialize(deserializer)?;
+        Rule::compile(&source)
+            .map_err(|e| serde::de::Error::custom(format!("invalid match rule {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn client() -> Value {
+        json!({
+            "class": "vesktop",
+            "title": "#general - Discord",
+            "pid": 4242,
+            "size": [1280, 720],
+            "floating": false,
+            "workspace": { "id": 3, "name": "3" },
+            "tags": ["chat", "electron"]
+        })
+    }
+
+    fn matches(value: &Value, rule: &Value) -> bool {
+        Rule::compile(rule).unwrap().matches(value)
+    }
+
+    #[test]
+    fn test_plain_rules_keep_substring_and_containment() {
+        assert!(matches(&client(), &json!({"title": "Discord"})));
+        assert!(matches(&client(), &json!({"tags": ["chat"]})));
+        assert!(matches(&client(), &json!({"workspace": {"name": "3"}})));
+        assert!(!matches(&client(), &json!({"class": "discord"})));
+        assert!(!matches(&client(), &json!({"missing": "x"})));
+    }
+
+    #[test]
+    fn test_regex() {
+        let rule = json!({"class": {"regex": "^(discord|vesktop)$"}});
+        assert!(matches(&client(), &rule));
+        assert!(!matches(
+            &client(),
+            &json!({"class": {"regex": "^discord$"}})
+        ));
+        assert!(matches(&client(), &json!({"pid": {"regex": "^42"}})));
+    }
+
+    #[test]
+    fn test_not() {
+        assert!(matches(&client(), &json!({"class": {"not": "discord"}})));
+        assert!(!matches(&client(), &json!({"class": {"not": "vesk"}})));
+        assert!(matches(&client(), &json!({"not": {"floating": true}})));
+    }
+
+    #[test]
+    fn test_any_and_all() {
+        let any = json!({"any": [{"class": "discord"}, {"class": "vesktop"}]});
+        let all = json!({"all": [{"class": "vesktop"}, {"title": "Discord"}]});
+        let all_fail = json!({"all": [{"class": "vesktop"}, {"title": "Slack"}]});
+
+        assert!(matches(&client(), &any));
+        assert!(matches(&client(), &all));
+        assert!(!matches(&client(), &all_fail));
+        assert!(matches(
+            &client(),
+            &json!({"class": {"any": ["cord", "top"]}})
+        ));
+    }
+
+    #[test]
+    fn test_eq_is_exact() {
+        assert!(matches(&client(), &json!({"class": {"eq": "vesktop"}})));
+        assert!(!matches(&client(), &json!({"class": {"eq": "vesk"}})));
+    }
+
+    #[test]
+    fn test_numeric_comparisons() {
+        assert!(matches(
+            &client(),
+            &json!({"pid": {"gt": 4000, "lt": 5000}})
+        ));
+        assert!(matches(
+            &client(),
+            &json!({"pid": {"gte": 4242, "lte": 4242}})
+        ));
+        assert!(!matches(&client(), &json!({"pid": {"gt": 4242}})));
+        assert!(!matches(&client(), &json!({"class": {"gt": 1}})));
+    }
+
+    #[test]
+    fn test_size_comparisons() {
+        assert!(matches(&client(), &json!({"size": {"gte": [1000, 700]}})));
+        assert!(!matches(&client(), &json!({"size": {"gte": [1000, 800]}})));
+        assert!(matches(&client(), &json!({"size": {"0": {"gt": 1200}}})));
+        assert!(!matches(&client(), &json!({"size": {"5": {"gt": 0}}})));
+    }
+
+    #[test]
+    fn test_compile_reports_problems() {
+        let err = |rule: Value| Rule::compile(&rule).unwrap_err();
+
+        let bad_regex = err(json!({"class": {"regex": "("}}));
+        assert_eq!(bad_regex.path, "/class/regex");
+        assert!(bad_regex.problem.contains("invalid regex"));
+        assert_eq!(err(json!({"size": {"gte": "big"}})).path, "/size/gte");
+        assert_eq!(err(json!({"any": 3})).path, "/any");
+        assert!(Rule::compile(&json!({"class": {"regex": "^a$"}})).is_ok());
+    }
+}
@@ -1,7 +1,12 @@
+pub mod config;
 pub mod gen_scheme;
 pub mod hypr;
+pub mod launcher;
+pub mod matcher;
 pub mod math;
 pub mod notify;
 pub mod palettes;
 pub mod paths;
+pub mod qs;
 pub mod score;
+pub mod wallpaper;
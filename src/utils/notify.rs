@@ -1,43 +1,440 @@
-use std::process::{Command, Stdio};
-use std::io::{self, Write};
-
-pub fn notify(args: &[&str]) -> io::Result<String> {
-    let output = Command::new("notify-send")
-        .arg("-a")
-        .arg("ferret-cli")
-        .args(args)
-        .output()?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!("notify-send failed: {}", stderr),
-        ));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    Ok(stdout.trim().to_string())
-}
-
-pub fn close_notification(identifier: &str) -> io::Result<()> {
-    let status = Command::new("gdbus")
-        .arg("call")
-        .arg("--session")
-        .arg("--dest=org.freedesktop.Notifications")
-        .arg("--object-path=/org/freedesktop/Notifications")
-        .arg("--method=org.freedesktop.Notifications.CloseNotification")
-        .arg(identifier)
-        .stdout(Stdio::null()) 
-        .stderr(Stdio::null())
-        .status()?;
-
-    if !status.success() {
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Failed to close notification via gdbus",
-        ));
-    }
-
-    Ok(())
+//! Desktop notifications through `org.freedesktop.Notifications` on the session bus,
+//! filtered by the `notifications` preferences in `cli.json`.
+
+use chrono::{Local, NaiveTime};
+use serde::{Deserialize, Deserializer};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::Value;
+
+use crate::utils::config::ConfigIssue;
+use crate::utils::paths::Paths;
+
+const APP_NAME: &str = "ferret-cli";
+
+const DESTINATION: &str = "org.freedesktop.Notifications";
+const OBJECT_PATH: &str = "/org/freedesktop/Notifications";
+const INTERFACE: &str = "org.freedesktop.Notifications";
+
+#[derive(Debug)]
+pub enum NotifyError {
+    Bus(zbus::Error),
+    /// The bus connection closed while waiting for the user to act on a notification.
+    Disconnected,
+}
+
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotifyError::Bus(e) => write!(f, "notification failed: {}", e),
+            NotifyError::Disconnected => write!(f, "notification daemon went away"),
+        }
+    }
+}
+
+impl Error for NotifyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NotifyError::Bus(e) => Some(e),
+            NotifyError::Disconnected => None,
+        }
+    }
+}
+
+impl From<zbus::Error> for NotifyError {
+    fn from(e: zbus::Error) -> Self {
+        NotifyError::Bus(e)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+/// What a notification is about, for turning kinds of notifications off in `cli.json`.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum Category {
+    /// Dynamic schemes that could not be made from a new wallpaper.
+    SchemeErrors,
+    /// Wallpaper changes made with `ferret wallpaper`.
+    Wallpaper,
+    Recording,
+    Screenshots,
+    /// Shell crashes reported by `ferret shell --supervise`.
+    Shell,
+    /// Apps that `ferret toggle --wait` could not start, and `--prestart` progress.
+    Toggle,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct CategoryConfig {
+    pub enabled: bool,
+    /// Replaces the urgency ferret picks for the category.
+    pub urgency: Option<Urgency>,
+}
+
+impl Default for CategoryConfig {
+    fn default() -> Self {
+        CategoryConfig {
+            enabled: true,
+            urgency: None,
+        }
+    }
+}
+
+fn clock_time<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveTime, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    NaiveTime::parse_from_str(&s, "%H:%M")
+        .map_err(|_| serde::de::Error::custom(format!("expected a time like 22:30, got {:?}", s)))
+}
+
+/// A daily window, which may wrap past midnight, in which notifications are held back.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHours {
+    #[serde(deserialize_with = "clock_time")]
+    pub from: NaiveTime,
+    #[serde(deserialize_with = "clock_time")]
+    pub to: NaiveTime,
+    /// Whether critical notifications still get through.
+    #[serde(default = "allow_critical_default")]
+    pub allow_critical: bool,
+}
+
+fn allow_critical_default() -> bool {
+    true
+}
+
+impl QuietHours {
+    fn contains(&self, time: NaiveTime) -> bool {
+        if self.from <= self.to {
+            self.from <= time && time < self.to
+        } else {
+            time >= self.from || time < self.to
+        }
+    }
+}
+
+/// The `notifications` section of `cli.json`.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotifyConfig {
+    pub categories: HashMap<Category, CategoryConfig>,
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl NotifyConfig {
+    pub fn from_user(user: Option<&JsonValue>, paths: &Paths) -> Self {
+        let Some(section) = user.and_then(|v| v.get("notifications")) else {
+            return NotifyConfig::default();
+        };
+        serde_json::from_value(section.clone()).unwrap_or_else(|e| {
+            ConfigIssue::new(&paths.user_config_path, "/notifications", e.to_string()).warn();
+            NotifyConfig::default()
+        })
+    }
+
+    /// The notification as it should be sent at `now`, or `None` if it is turned off or
+    /// falls in quiet hours.
+    fn apply(&self, notification: &Notification, now: NaiveTime) -> Option<Notification> {
+        let mut notification = notification.clone();
+        if let Some(category) = notification.category.and_then(|c| self.categories.get(&c)) {
+            if !category.enabled {
+                return None;
+            }
+            if let Some(urgency) = category.urgency {
+                notification.urgency = urgency;
+            }
+        }
+
+        let quiet = self.quiet_hours.as_ref().is_some_and(|quiet| {
+            quiet.contains(now)
+                && !(quiet.allow_critical && notification.urgency == Urgency::Critical)
+        });
+        (!quiet).then_some(notification)
+    }
+}
+
+/// A notification to send, built up with its setters.
+#[derive(Debug, Clone, Default)]
+pub struct Notification {
+    category: Option<Category>,
+    summary: String,
+    body: String,
+    icon: String,
+    urgency: Urgency,
+    /// Milliseconds; -1 leaves it to the server and 0 never expires.
+    timeout: i32,
+    replaces: u32,
+    /// `(key, label)` pairs, shown as buttons by most servers.
+    actions: Vec<(String, String)>,
+    value: Option<u8>,
+}
+
+impl Notification {
+    pub fn new(summary: impl Into<String>) -> Self {
+        Notification {
+            summary: summary.into(),
+            timeout: -1,
+            ..Default::default()
+        }
+    }
+
+    pub fn category(mut self, category: Category) -> Self {
+        self.category = Some(category);
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// An icon name from the theme or a `file://` URI.
+    pub fn icon(mut self, icon: impl Into<String>) -> Self {
+        self.icon = icon.into();
+        self
+    }
+
+    pub fn urgency(mut self, urgency: Urgency) -> Self {
+        self.urgency = urgency;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout.as_millis().min(i32::MAX as u128) as i32;
+        self
+    }
+
+    /// Keeps the notification up until it is dismissed.
+    pub fn persistent(mut self) -> Self {
+        self.timeout = 0;
+        self
+    }
+
+    /// Updates the notification `id` in place instead of showing a new one.
+    pub fn replaces(mut self, id: u32) -> Self {
+        self.replaces = id;
+        self
+    }
+
+    pub fn action(mut self, key: impl Into<String>, label: impl Into<String>) -> Self {
+        self.actions.push((key.into(), label.into()));
+        self
+    }
+
+    /// Progress in percent, drawn as a bar by servers that support the `value` hint.
+    pub fn value(mut self, percent: u8) -> Self {
+        self.value = Some(percent.min(100));
+        self
+    }
+
+    /// Actions flattened to the `[key, label, key, label, …]` list `Notify` takes.
+    fn action_list(&self) -> Vec<&str> {
+        self.actions
+            .iter()
+            .flat_map(|(key, label)| [key.as_str(), label.as_str()])
+            .collect()
+    }
+
+    fn hints(&self) -> HashMap<&'static str, Value<'static>> {
+        let mut hints = HashMap::new();
+        let urgency: u8 = match self.urgency {
+            Urgency::Low => 0,
+            Urgency::Normal => 1,
+            Urgency::Critical => 2,
+        };
+        hints.insert("urgency", Value::U8(urgency));
+        if let Some(value) = self.value {
+            hints.insert("value", Value::I32(value.into()));
+        }
+        hints
+    }
+}
+
+/// A connection to the notification server that applies the user's preferences.
+pub struct Notifier {
+    proxy: Proxy<'static>,
+    config: NotifyConfig,
+}
+
+impl Notifier {
+    pub fn connect(config: &NotifyConfig) -> Result<Self, NotifyError> {
+        let connection = Connection::session()?;
+        let proxy = Proxy::new(&connection, DESTINATION, OBJECT_PATH, INTERFACE)?;
+        Ok(Notifier {
+            proxy,
+            config: config.clone(),
+        })
+    }
+
+    /// Shows `notification` and returns its id, for replacing or closing it later, or
+    /// `None` if the preferences hold it back.
+    pub fn send(&self, notification: &Notification) -> Result<Option<u32>, NotifyError> {
+        let Some(notification) = self.config.apply(notification, Local::now().time()) else {
+            return Ok(None);
+        };
+
+        let id = self.proxy.call(
+            "Notify",
+            &(
+                APP_NAME,
+                notification.replaces,
+                notification.icon.as_str(),
+                notification.summary.as_str(),
+                notification.body.as_str(),
+                notification.action_list(),
+                notification.hints(),
+                notification.timeout,
+            ),
+        )?;
+        Ok(Some(id))
+    }
+
+    pub fn close(&self, id: u32) -> Result<(), NotifyError> {
+        self.proxy.call::<_, _, ()>("CloseNotification", &(id,))?;
+        Ok(())
+    }
+
+    /// Shows `notification` and blocks until it is closed, one of its actions is picked or
+    /// `timeout` passes, calling `on_action` with the key of the action. A notification still
+    /// up after `timeout` is closed, since nothing would answer its actions. Returns the
+    /// notification id, or `None` straight away if the preferences hold it back.
+    pub fn send_and_wait(
+        &self,
+        notification: &Notification,
+        timeout: Duration,
+        on_action: impl FnOnce(&str),
+    ) -> Result<Option<u32>, NotifyError> {
+        // Subscribe first so a quick click cannot slip past.
+        let signals = self.proxy.receive_all_signals()?;
+        let Some(id) = self.send(notification)? else {
+            return Ok(None);
+        };
+
+        // Reading signals blocks, so it happens on a thread that is left behind if the
+        // timeout passes first.
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for message in signals {
+                let header = message.header();
+                let event = match header.member().map(|m| m.as_str()) {
+                    Some("ActionInvoked") => message
+                        .body()
+                        .deserialize::<(u32, String)>()
+                        .map(|(signal_id, key)| (signal_id, Some(key))),
+                    Some("NotificationClosed") => message
+                        .body()
+                        .deserialize::<(u32, u32)>()
+                        .map(|(signal_id, _reason)| (signal_id, None)),
+                    _ => continue,
+                };
+                match event {
+                    Ok((signal_id, _)) if signal_id != id => {}
+                    Ok((_, key)) => {
+                        let _ = tx.send(Ok(key));
+                        return;
+                    }
+                    Err(e) => {
+                        let _ = tx.send(Err(e));
+                        return;
+                    }
+                }
+            }
+        });
+
+        match rx.recv_timeout(timeout) {
+            Ok(Ok(key)) => {
+                if let Some(key) = key {
+                    on_action(&key);
+                }
+                Ok(Some(id))
+            }
+            Ok(Err(e)) => Err(e.into()),
+            Err(RecvTimeoutError::Timeout) => {
+                self.close(id)?;
+                Ok(Some(id))
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(NotifyError::Disconnected),
+        }
+    }
+}
+
+/// Shows `notification` on a fresh connection and returns its id, unless the preferences
+/// hold it back.
+pub fn notify(
+    notification: &Notification,
+    config: &NotifyConfig,
+) -> Result<Option<u32>, NotifyError> {
+    Notifier::connect(config)?.send(notification)
+}
+
+pub fn close_notification(id: u32) -> Result<(), NotifyError> {
+    Notifier::connect(&NotifyConfig::default())?.close(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_actions_and_hints() {
+        let notification = Notification::new("Screenshot saved")
+            .urgency(Urgency::Critical)
+            .action("open", "Open")
+            .action("delete", "Delete")
+            .value(140);
+
+        assert_eq!(
+            notification.action_list(),
+            vec!["open", "Open", "delete", "Delete"]
+        );
+        let hints = notification.hints();
+        assert_eq!(hints["urgency"], Value::U8(2));
+        assert_eq!(hints["value"], Value::I32(100));
+        assert_eq!(notification.timeout, -1);
+        assert_eq!(notification.timeout(Duration::from_secs(5)).timeout, 5000);
+    }
+
+    #[test]
+    fn test_preferences() {
+        let config: NotifyConfig = serde_json::from_value(serde_json::json!({
+            "categories": {
+                "screenshots": { "enabled": false },
+                "recording": { "urgency": "low" }
+            },
+            "quietHours": { "from": "22:00", "to": "07:30" }
+        }))
+        .unwrap();
+        let at = |s| NaiveTime::parse_from_str(s, "%H:%M").unwrap();
+        let noon = at("12:00");
+
+        let screenshot = Notification::new("Saved").category(Category::Screenshots);
+        assert!(config.apply(&screenshot, noon).is_none());
+
+        let recording = Notification::new("Recording").category(Category::Recording);
+        let sent = config.apply(&recording, noon).unwrap();
+        assert_eq!(sent.urgency, Urgency::Low);
+
+        let crash = Notification::new("Crashed")
+            .category(Category::Shell)
+            .urgency(Urgency::Critical);
+        assert!(config.apply(&crash, at("23:15")).is_some());
+        assert!(config.apply(&recording, at("23:15")).is_none());
+        assert!(config.apply(&recording, at("03:00")).is_none());
+        assert!(config.apply(&recording, at("07:30")).is_some());
+    }
 }
@@ -1,7 +1,8 @@
+use chrono::Local;
 use serde::Serialize;
 use sha2::{Digest, Sha256};
 use std::env;
-use std::fs::{self, File};
+use std::fs::{self, File, TryLockError};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use tempfile::NamedTempFile;
@@ -34,14 +35,27 @@ pub struct Paths {
     pub wallpaper_path_path: PathBuf,
     pub wallpaper_link_path: PathBuf,
     pub wallpaper_thumbnail_path: PathBuf,
+    pub wallpaper_history_path: PathBuf,
     pub wallpapers_cache_dir: PathBuf,
 
+    pub specialws_state_path: PathBuf,
+    pub prestart_lock_path: PathBuf,
+
+    pub shell_state_dir: PathBuf,
+    pub supervisor_state_path: PathBuf,
+    pub supervisor_stop_path: PathBuf,
+    pub supervisor_lock_path: PathBuf,
+    pub shell_logs_dir: PathBuf,
+    pub shell_config_dir: PathBuf,
+
     pub screenshots_dir: PathBuf,
     pub screenshots_cache_dir: PathBuf,
 
     pub recordings_dir: PathBuf,
     pub recording_path: PathBuf,
     pub recording_notif_path: PathBuf,
+    pub recording_lock_path: PathBuf,
+    pub recorder_log_path: PathBuf,
 }
 
 impl Paths {
@@ -88,8 +102,19 @@ impl Paths {
         let wallpaper_path_path = f_state_dir.join("wallpaper/path.txt");
         let wallpaper_link_path = f_state_dir.join("wallpaper/current");
         let wallpaper_thumbnail_path = f_state_dir.join("wallpaper/thumbnail.jpg");
+        let wallpaper_history_path = f_state_dir.join("wallpaper/history.json");
         let wallpapers_cache_dir = f_cache_dir.join("wallpapers");
 
+        let specialws_state_path = f_state_dir.join("specialws.json");
+        let prestart_lock_path = f_state_dir.join("prestart.lock");
+
+        let shell_state_dir = f_state_dir.join("shell");
+        let supervisor_state_path = shell_state_dir.join("supervisor.json");
+        let supervisor_stop_path = shell_state_dir.join("supervisor.stop");
+        let supervisor_lock_path = shell_state_dir.join("supervisor.lock");
+        let shell_logs_dir = f_state_dir.join("logs");
+        let shell_config_dir = config_dir.join("quickshell/ferret");
+
         let screenshots_dir =
             get_env_path("FERRET_SCREENSHOTS_DIR", pictures_dir.join("Screenshots"));
         let screenshots_cache_dir = f_cache_dir.join("screenshots");
@@ -97,6 +122,8 @@ impl Paths {
         let recordings_dir = get_env_path("FERRET_RECORDINGS_DIR", videos_dir.join("Recordings"));
         let recording_path = f_state_dir.join("record/recording.mp4");
         let recording_notif_path = f_state_dir.join("record/notifid.txt");
+        let recording_lock_path = f_state_dir.join("record/record.lock");
+        let recorder_log_path = f_state_dir.join("record/recorder.log");
 
         Self {
             config_dir,
@@ -121,12 +148,23 @@ impl Paths {
             wallpaper_path_path,
             wallpaper_link_path,
             wallpaper_thumbnail_path,
+            wallpaper_history_path,
             wallpapers_cache_dir,
+            specialws_state_path,
+            prestart_lock_path,
+            shell_state_dir,
+            supervisor_state_path,
+            supervisor_stop_path,
+            supervisor_lock_path,
+            shell_logs_dir,
+            shell_config_dir,
             screenshots_dir,
             screenshots_cache_dir,
             recordings_dir,
             recording_path,
             recording_notif_path,
+            recording_lock_path,
+            recorder_log_path,
         }
     }
 }
@@ -141,7 +179,13 @@ pub fn compute_hash<P: AsRef<Path>>(path: P) -> io::Result<String> {
     Ok(hex::encode(hash))
 }
 
-pub fn atomic_dump<P: AsRef<Path>, T: Serialize>(path: P, content: &T) -> io::Result<()> {
+/// A temporary file next to `path` holding what `write` puts in it, which replaces `path`
+/// once persisted. Lets several files be prepared before any of them changes.
+pub fn staged_file<P, F>(path: P, write: F) -> io::Result<NamedTempFile>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut NamedTempFile) -> io::Result<()>,
+{
     let path = path.as_ref();
 
     if let Some(parent) = path.parent() {
@@ -151,21 +195,93 @@ pub fn atomic_dump<P: AsRef<Path>, T: Serialize>(path: P, content: &T) -> io::Re
     let dir = path.parent().unwrap_or_else(|| Path::new("."));
     let mut temp_file = NamedTempFile::new_in(dir)?;
 
-    serde_json::to_writer(&mut temp_file, content)?;
+    write(&mut temp_file)?;
 
     temp_file.flush()?;
 
-    temp_file.persist(path).map_err(|e| e.error)?;
+    Ok(temp_file)
+}
+
+pub fn staged_dump<P: AsRef<Path>, T: Serialize>(
+    path: P,
+    content: &T,
+) -> io::Result<NamedTempFile> {
+    staged_file(path, |f| Ok(serde_json::to_writer(f, content)?))
+}
+
+pub fn staged_write<P: AsRef<Path>>(path: P, content: &str) -> io::Result<NamedTempFile> {
+    staged_file(path, |f| f.write_all(content.as_bytes()))
+}
 
+pub fn atomic_dump<P: AsRef<Path>, T: Serialize>(path: P, content: &T) -> io::Result<()> {
+    staged_dump(&path, content)?
+        .persist(path)
+        .map_err(|e| e.error)?;
     Ok(())
 }
 
+fn open_lock_file(path: &Path) -> io::Result<File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)
+}
+
+/// Opens `path` and takes an exclusive lock on it, waiting for other holders. The lock is
+/// released when the returned file is dropped.
+pub fn lock_file<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    let file = open_lock_file(path.as_ref())?;
+    file.lock()?;
+    Ok(file)
+}
+
+/// Like `lock_file`, but returns `None` straight away when someone else holds the lock.
+pub fn try_lock_file<P: AsRef<Path>>(path: P) -> io::Result<Option<File>> {
+    let file = open_lock_file(path.as_ref())?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(e)) => Err(e),
+    }
+}
+
+/// A free `<prefix>_<YYYYmmdd_HHMMSS>.<extension>` path in `dir`, numbered when several are
+/// made within a second.
+pub fn timestamped_path(dir: &Path, prefix: &str, extension: &str) -> PathBuf {
+    let stem = format!("{}_{}", prefix, Local::now().format("%Y%m%d_%H%M%S"));
+    let mut path = dir.join(format!("{}.{}", stem, extension));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{}_{}.{}", stem, n, extension));
+        n += 1;
+    }
+    path
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::io::Write;
     use tempfile::tempdir;
 
+    #[test]
+    fn test_timestamped_path_avoids_existing_files() {
+        let dir = tempdir().unwrap();
+
+        let first = timestamped_path(dir.path(), "screenshot", "png");
+        fs::write(&first, "").unwrap();
+        let second = timestamped_path(dir.path(), "screenshot", "png");
+
+        let name = first.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("screenshot_") && name.ends_with(".png"));
+        assert_eq!(name.len(), "screenshot_20240501_143000.png".len());
+        assert_ne!(first, second);
+    }
+
     #[test]
     fn test_compute_hash_basic() {
         let mut temp_file = tempfile::NamedTempFile::new().unwrap();
@@ -224,6 +340,28 @@ mod tests {
         assert!(file_path.exists());
     }
 
+    #[test]
+    fn test_staged_write_replaces_only_once_persisted() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("wallpaper").join("path.txt");
+
+        staged_write(&file_path, "/tmp/first.png")
+            .unwrap()
+            .persist(&file_path)
+            .unwrap();
+        let staged = staged_write(&file_path, "/tmp/second.png").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&file_path).unwrap(),
+            "/tmp/first.png"
+        );
+
+        staged.persist(&file_path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(file_path).unwrap(),
+            "/tmp/second.png"
+        );
+    }
+
     #[test]
     fn test_app_paths_structure() {
         let temp_home = tempdir().unwrap();
@@ -0,0 +1,304 @@
+//! Client for the IPC socket of the running `qs -c ferret` instance, used in place of
+//! spawning `qs ipc ...` for every call. The socket protocol is private to Quickshell, so
+//! when the socket can't take a request it is made through the `qs` command line instead.
+
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufReader, BufWriter};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+pub mod models;
+pub mod wire;
+
+use models::Target;
+use wire::{Reader, Writer};
+
+/// The Quickshell config ferret's shell runs as (`qs -c ferret`).
+pub const CONFIG_NAME: &str = "ferret";
+
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Overrides socket discovery, mainly for talking to a specific instance.
+const SOCKET_ENV: &str = "FERRET_QS_SOCKET";
+
+#[derive(Debug)]
+pub enum QsError {
+    Io(io::Error),
+    /// No running shell instance was found.
+    NotRunning,
+    /// The shell is running but its config failed to load.
+    NoGeneration,
+    TargetNotFound(String),
+    FunctionNotFound {
+        target: String,
+        function: String,
+    },
+    /// The call was rejected, e.g. for a wrong argument count or type.
+    InvalidCall {
+        target: String,
+        function: String,
+        reason: String,
+    },
+    Protocol(String),
+    /// The `qs` command line fallback failed.
+    Cli(String),
+}
+
+impl fmt::Display for QsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QsError::Io(e) => write!(f, "shell IPC failed: {}", e),
+            QsError::NotRunning => {
+                write!(f, "the shell is not running (start it with `ferret shell`)")
+            }
+            QsError::NoGeneration => write!(f, "the shell has no loaded config"),
+            QsError::TargetNotFound(target) => write!(f, "no IPC target named {}", target),
+            QsError::FunctionNotFound { target, function } => {
+                write!(f, "target {} has no function {}", target, function)
+            }
+            QsError::InvalidCall {
+                target,
+                function,
+                reason,
+            } => write!(f, "{}.{} rejected the call: {}", target, function, reason),
+            QsError::Protocol(msg) => write!(f, "unexpected reply from the shell: {}", msg),
+            QsError::Cli(msg) => write!(f, "qs failed: {}", msg),
+        }
+    }
+}
+
+impl Error for QsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            QsError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for QsError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused => QsError::NotRunning,
+            io::ErrorKind::UnexpectedEof => {
+                QsError::Protocol("connection closed mid-reply".to_string())
+            }
+            _ => QsError::Io(e),
+        }
+    }
+}
+
+/// Whether a process command line is a Quickshell instance running `config`.
+fn runs_config(cmdline: &[&str], config: &str) -> bool {
+    let Some((exe, args)) = cmdline.split_first() else {
+        return false;
+    };
+    let exe = Path::new(exe).file_name().and_then(|n| n.to_str());
+    if !matches!(exe, Some("qs" | "quickshell")) {
+        return false;
+    }
+
+    args.iter().enumerate().any(|(i, arg)| match *arg {
+        "-c" | "--config" => args.get(i + 1) == Some(&config),
+        other => other.strip_prefix("--config=") == Some(config),
+    })
+}
+
+/// PIDs of running `qs -c <config>` processes.
+pub fn instance_pids(config: &str) -> Vec<u32> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            let raw = fs::read(entry.path().join("cmdline")).ok()?;
+            let cmdline = String::from_utf8_lossy(&raw);
+            let args: Vec<&str> = cmdline.split('\0').filter(|a| !a.is_empty()).collect();
+            runs_config(&args, config).then_some(pid)
+        })
+        .collect()
+}
+
+/// The IPC socket of the running shell: `$XDG_RUNTIME_DIR/quickshell/by-pid/<pid>/ipc.sock`
+/// of the first `qs -c ferret` process that has one.
+pub fn socket_path() -> Result<PathBuf, QsError> {
+    if let Ok(path) = env::var(SOCKET_ENV) {
+        return Ok(PathBuf::from(path));
+    }
+
+    let runtime_dir = env::var("XDG_RUNTIME_DIR").map_err(|e| {
+        QsError::Io(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("XDG_RUNTIME_DIR not set: {}", e),
+        ))
+    })?;
+    let by_pid = PathBuf::from(runtime_dir).join("quickshell/by-pid");
+
+    instance_pids(CONFIG_NAME)
+        .into_iter()
+        .map(|pid| by_pid.join(pid.to_string()).join("ipc.sock"))
+        .find(|path| path.exists())
+        .ok_or(QsError::NotRunning)
+}
+
+struct Connection {
+    reader: Reader<BufReader<UnixStream>>,
+    writer: Writer<BufWriter<UnixStream>>,
+}
+
+fn connect() -> Result<Connection, QsError> {
+    let stream = UnixStream::connect(socket_path()?)?;
+    stream.set_read_timeout(Some(SOCKET_TIMEOUT))?;
+    stream.set_write_timeout(Some(SOCKET_TIMEOUT))?;
+
+    Ok(Connection {
+        reader: Reader::new(BufReader::new(stream.try_clone()?)),
+        writer: Writer::new(BufWriter::new(stream)),
+    })
+}
+
+/// Connects and sends one request written by `write`. Until this returns `Ok` the shell
+/// has not seen a whole request, so it is safe to make it again some other way.
+fn send(
+    write: impl FnOnce(&mut Writer<BufWriter<UnixStream>>) -> io::Result<()>,
+) -> Result<Connection, QsError> {
+    let mut conn = connect()?;
+    write(&mut conn.writer)?;
+    conn.writer.flush()?;
+    Ok(conn)
+}
+
+/// Reads a response header, turning the error variants into `QsError`s. Returns whether a
+/// value follows.
+fn read_status(conn: &mut Connection, target: &str, function: &str) -> Result<bool, QsError> {
+    match conn.reader.u32()? {
+        wire::RESP_VOID => Ok(false),
+        wire::RESP_VALUE => Ok(true),
+        wire::RESP_NO_GENERATION => Err(QsError::NoGeneration),
+        wire::RESP_TARGET_NOT_FOUND => Err(QsError::TargetNotFound(target.to_string())),
+        wire::RESP_ENTRY_NOT_FOUND => Err(QsError::FunctionNotFound {
+            target: target.to_string(),
+            function: function.to_string(),
+        }),
+        wire::RESP_INVALID_CALL => Err(QsError::InvalidCall {
+            target: target.to_string(),
+            function: function.to_string(),
+            reason: conn.reader.string()?,
+        }),
+        other => Err(QsError::Protocol(format!(
+            "unknown response type {}",
+            other
+        ))),
+    }
+}
+
+/// Whether an error means the socket could not be used, rather than the shell answering.
+fn is_socket_failure(e: &QsError) -> bool {
+    matches!(e, QsError::Protocol(_) | QsError::Io(_))
+}
+
+/// Runs `qs -c ferret <args>`, returning what it printed.
+fn qs_cli(args: &[&str]) -> Result<String, QsError> {
+    let output = Command::new("qs")
+        .args(["-c", CONFIG_NAME])
+        .args(args)
+        .output()
+        .map_err(QsError::Io)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(QsError::Cli(stderr.trim().to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Calls `target.function(args…)`, returning its result, or `None` for void functions.
+/// Only falls back to `qs ipc call` when the request never reached the shell; once it has,
+/// making the call again could run the function twice.
+pub fn call(target: &str, function: &str, args: &[String]) -> Result<Option<String>, QsError> {
+    let sent = send(|w| {
+        w.u32(wire::CMD_STRING_CALL)?;
+        w.string(target)?;
+        w.string(function)?;
+        w.strings(args)
+    });
+    let mut conn = match sent {
+        Ok(conn) => conn,
+        Err(e) if is_socket_failure(&e) => return call_cli(target, function, args),
+        Err(e) => return Err(e),
+    };
+
+    if read_status(&mut conn, target, function)? {
+        Ok(Some(conn.reader.string()?))
+    } else {
+        Ok(None)
+    }
+}
+
+fn call_cli(target: &str, function: &str, args: &[String]) -> Result<Option<String>, QsError> {
+    let mut cli_args = vec!["ipc", "call", target, function];
+    cli_args.extend(args.iter().map(String::as_str));
+    let output = qs_cli(&cli_args)?;
+    let result = output.strip_suffix('\n').unwrap_or(&output);
+    Ok((!result.is_empty()).then(|| result.to_string()))
+}
+
+/// Every IPC target the shell exposes, as `qs ipc show` lists them. Listing has no side
+/// effects, so unlike `call` this also falls back when the reply can't be decoded.
+pub fn targets() -> Result<Vec<Target>, QsError> {
+    match targets_socket() {
+        Err(e) if is_socket_failure(&e) => Target::parse_show(&qs_cli(&["ipc", "show"])?)
+            .ok_or_else(|| QsError::Cli("could not parse `qs ipc show`".to_string())),
+        other => other,
+    }
+}
+
+fn targets_socket() -> Result<Vec<Target>, QsError> {
+    let mut conn = send(|w| {
+        w.u32(wire::CMD_QUERY_METADATA)?;
+        w.string("")?;
+        w.string("")
+    })?;
+
+    if !read_status(&mut conn, "", "")? {
+        return Ok(Vec::new());
+    }
+    Ok(conn.reader.vec(Target::read)?)
+}
+
+/// Asks the shell to quit and waits for it to drop the connection. Falls back to `qs kill`
+/// only when the request could not be sent.
+pub fn kill() -> Result<(), QsError> {
+    let mut conn = match send(|w| w.u32(wire::CMD_KILL)) {
+        Ok(conn) => conn,
+        Err(e) if is_socket_failure(&e) => return qs_cli(&["kill"]).map(|_| ()),
+        Err(e) => return Err(e),
+    };
+    conn.reader.drain()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runs_config() {
+        assert!(runs_config(
+            &["/usr/bin/qs", "-c", "ferret", "-n"],
+            "ferret"
+        ));
+        assert!(runs_config(&["quickshell", "--config=ferret"], "ferret"));
+        assert!(!runs_config(&["qs", "-c", "other"], "ferret"));
+        assert!(!runs_config(&["vim", "-c", "ferret"], "ferret"));
+        assert!(!runs_config(&[], "ferret"));
+    }
+}
@@ -0,0 +1,185 @@
+use serde::Serialize;
+use std::fmt;
+use std::io::{self, Read};
+
+use super::wire::Reader;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Argument {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub arguments: Vec<Argument>,
+    #[serde(rename = "returnType")]
+    pub return_type: String,
+}
+
+/// Parses `name: type`, as arguments and properties are printed.
+fn parse_typed(text: &str) -> Option<(String, String)> {
+    let (name, ty) = text.split_once(':')?;
+    Some((name.trim().to_string(), ty.trim().to_string()))
+}
+
+impl Function {
+    /// Parses a signature as `ipc show` prints it, the inverse of `signature`.
+    fn parse(signature: &str) -> Option<Self> {
+        let (name, rest) = signature.split_once('(')?;
+        let (args, return_type) = rest.rsplit_once("):")?;
+        let arguments = args
+            .split(',')
+            .filter(|a| !a.trim().is_empty())
+            .map(|a| parse_typed(a).map(|(name, ty)| Argument { name, ty }))
+            .collect::<Option<_>>()?;
+
+        Some(Function {
+            name: name.trim().to_string(),
+            arguments,
+            return_type: return_type.trim().to_string(),
+        })
+    }
+
+    /// The signature as `ipc show` prints it, e.g. `set(path: string): void`.
+    pub fn signature(&self) -> String {
+        let args: Vec<String> = self
+            .arguments
+            .iter()
+            .map(|a| format!("{}: {}", a.name, a.ty))
+            .collect();
+        format!("{}({}): {}", self.name, args.join(", "), self.return_type)
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Property {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+/// An `IpcHandler` registered by the shell, with everything callable on it.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Target {
+    pub name: String,
+    pub functions: Vec<Function>,
+    pub properties: Vec<Property>,
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "target {}", self.name)?;
+        for function in &self.functions {
+            writeln!(f, "  function {}", function.signature())?;
+        }
+        for property in &self.properties {
+            writeln!(f, "  property {}: {}", property.name, property.ty)?;
+        }
+        Ok(())
+    }
+}
+
+impl Target {
+    /// Parses the output of `qs ipc show`, which lists targets the way `Display` prints them.
+    pub fn parse_show(text: &str) -> Option<Vec<Self>> {
+        let mut targets: Vec<Target> = Vec::new();
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            if let Some(name) = line.strip_prefix("target ") {
+                targets.push(Target {
+                    name: name.trim().to_string(),
+                    functions: Vec::new(),
+                    properties: Vec::new(),
+                });
+                continue;
+            }
+
+            let target = targets.last_mut()?;
+            let line = line.trim_start();
+            if let Some(signature) = line.strip_prefix("function ") {
+                target.functions.push(Function::parse(signature)?);
+            } else if let Some(property) = line.strip_prefix("property ") {
+                let (name, ty) = parse_typed(property)?;
+                target.properties.push(Property { name, ty });
+            } else {
+                return None;
+            }
+        }
+        Some(targets)
+    }
+
+    /// Decodes a `WireTargetDefinition` from a metadata response.
+    pub fn read<R: Read>(reader: &mut Reader<R>) -> io::Result<Self> {
+        let name = reader.string()?;
+        let functions = reader.vec(|r| {
+            let name = r.string()?;
+            let return_type = r.string()?;
+            let arguments = r.vec(|r| {
+                Ok(Argument {
+                    name: r.string()?,
+                    ty: r.string()?,
+                })
+            })?;
+            Ok(Function {
+                name,
+                arguments,
+                return_type,
+            })
+        })?;
+        let properties = reader.vec(|r| {
+            Ok(Property {
+                name: r.string()?,
+                ty: r.string()?,
+            })
+        })?;
+
+        Ok(Target {
+            name,
+            functions,
+            properties,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::wire::Writer;
+    use super::*;
+
+    #[test]
+    fn test_read_target_and_display() {
+        let mut buf = Vec::new();
+        let mut w = Writer::new(&mut buf);
+        w.string("wallpaper").unwrap();
+        w.u32(2).unwrap();
+        w.string("set").unwrap();
+        w.string("void").unwrap();
+        w.u32(1).unwrap();
+        w.string("path").unwrap();
+        w.string("string").unwrap();
+        w.string("get").unwrap();
+        w.string("string").unwrap();
+        w.u32(0).unwrap();
+        w.u32(1).unwrap();
+        w.string("current").unwrap();
+        w.string("string").unwrap();
+
+        let target = Target::read(&mut Reader::new(buf.as_slice())).unwrap();
+
+        assert_eq!(target.functions[0].signature(), "set(path: string): void");
+        assert_eq!(
+            target.to_string(),
+            "target wallpaper\n  function set(path: string): void\n  function get(): string\n  property current: string\n"
+        );
+        assert_eq!(Target::parse_show(&target.to_string()), Some(vec![target]));
+    }
+
+    #[test]
+    fn test_parse_show_rejects_unknown_lines() {
+        assert_eq!(Target::parse_show(""), Some(Vec::new()));
+        assert_eq!(Target::parse_show("  function get(): string\n"), None);
+        assert_eq!(Target::parse_show("target a\n  signal changed\n"), None);
+    }
+}
@@ -0,0 +1,139 @@
+//! The subset of Qt's `QDataStream` encoding that Quickshell's IPC socket speaks: big-endian
+//! integers, `QString` as a byte length followed by UTF-16BE, and `QVector` as a count followed
+//! by its items. Commands and responses are `std::variant`s, written as their index first.
+
+use std::io::{self, Read, Write};
+
+/// `QString` length marking a null string.
+const NULL_STRING: u32 = u32::MAX;
+
+/// Command variant indices, in the order Quickshell declares `IpcCommand`.
+pub const CMD_KILL: u32 = 1;
+pub const CMD_QUERY_METADATA: u32 = 2;
+pub const CMD_STRING_CALL: u32 = 3;
+
+/// Response variant indices shared by metadata queries and calls.
+pub const RESP_VOID: u32 = 0;
+pub const RESP_NO_GENERATION: u32 = 1;
+pub const RESP_TARGET_NOT_FOUND: u32 = 2;
+pub const RESP_ENTRY_NOT_FOUND: u32 = 3;
+pub const RESP_INVALID_CALL: u32 = 4;
+pub const RESP_VALUE: u32 = 5;
+
+pub struct Writer<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(inner: W) -> Self {
+        Writer { inner }
+    }
+
+    pub fn u32(&mut self, value: u32) -> io::Result<()> {
+        self.inner.write_all(&value.to_be_bytes())
+    }
+
+    pub fn string(&mut self, value: &str) -> io::Result<()> {
+        let utf16: Vec<u8> = value.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        self.u32(utf16.len() as u32)?;
+        self.inner.write_all(&utf16)
+    }
+
+    pub fn strings(&mut self, values: &[String]) -> io::Result<()> {
+        self.u32(values.len() as u32)?;
+        for value in values {
+            self.string(value)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+pub struct Reader<R: Read> {
+    inner: R,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(inner: R) -> Self {
+        Reader { inner }
+    }
+
+    pub fn u32(&mut self) -> io::Result<u32> {
+        let mut buf = [0u8; 4];
+        self.inner.read_exact(&mut buf)?;
+        Ok(u32::from_be_bytes(buf))
+    }
+
+    pub fn string(&mut self) -> io::Result<String> {
+        let len = self.u32()?;
+        if len == NULL_STRING {
+            return Ok(String::new());
+        }
+        if len % 2 != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("odd QString byte length {}", len),
+            ));
+        }
+
+        let mut buf = vec![0u8; len as usize];
+        self.inner.read_exact(&mut buf)?;
+        let units: Vec<u16> = buf
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16(&units).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Reads until the other end closes the connection.
+    pub fn drain(&mut self) -> io::Result<()> {
+        io::copy(&mut self.inner, &mut io::sink())?;
+        Ok(())
+    }
+
+    /// A `QVector` whose items are read by `item`.
+    pub fn vec<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> io::Result<T>,
+    ) -> io::Result<Vec<T>> {
+        let count = self.u32()?;
+        (0..count).map(|_| item(self)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_round_trip() {
+        let mut buf = Vec::new();
+        let mut writer = Writer::new(&mut buf);
+        writer.string("wallpaper ✓").unwrap();
+        writer.strings(&["a".to_string(), String::new()]).unwrap();
+
+        assert_eq!(&buf[..6], &[0, 0, 0, 22, 0, b'w']);
+
+        let mut reader = Reader::new(buf.as_slice());
+        assert_eq!(reader.string().unwrap(), "wallpaper ✓");
+        assert_eq!(
+            reader.vec(|r| r.string()).unwrap(),
+            vec!["a".to_string(), String::new()]
+        );
+    }
+
+    #[test]
+    fn test_null_string_reads_as_empty() {
+        let buf = u32::MAX.to_be_bytes();
+        assert_eq!(Reader::new(&buf[..]).string().unwrap(), "");
+    }
+
+    #[test]
+    fn test_truncated_string_is_an_error() {
+        let buf = [0, 0, 0, 4, 0, b'a'];
+        assert!(Reader::new(&buf[..]).string().is_err());
+    }
+}
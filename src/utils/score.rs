@@ -1,12 +1,19 @@
-use anyhow::Result;
-use image::GenericImageView;
+use anyhow::{Context, Result, bail};
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::io::Reader as ImageReader;
+use image::{AnimationDecoder, DynamicImage, GenericImageView, ImageFormat, Rgba};
 use material_colors::color::Argb;
 use material_colors::dislike::{fix_if_disliked, is_disliked};
 use material_colors::hct::Hct;
 use material_colors::quantize::Quantizer;
 use material_colors::quantize::QuantizerCelebi;
 use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::BufReader;
 use std::path::Path;
+use std::process::Command;
 
 use super::math::sanitize_degrees_int;
 
@@ -37,8 +44,8 @@ fn calculate_score(colors_to_population: &HashMap<u32, u32>, filter_enabled: boo
 
     let mut hue_excited_proportions = [0.0f64; 360];
 
-    for hue in 0..360 {
-        let proportion = hue_population[hue] as f64 / population_sum as f64;
+    for (hue, &population) in hue_population.iter().enumerate() {
+        let proportion = population as f64 / population_sum as f64;
         for i in (hue as i32 - 14)..=(hue as i32 + 16) {
             let neighbor_hue = sanitize_degrees_int(i) as usize;
             hue_excited_proportions[neighbor_hue] += proportion;
@@ -104,22 +111,24 @@ fn calculate_score(colors_to_population: &HashMap<u32, u32>, filter_enabled: boo
     }
 }
 
-pub fn score_image(image_path: &str) -> Result<Hct> {
-    let img = image::open(Path::new(image_path))?;
+const ANIMATION_SAMPLE_FRAMES: usize = 8;
+const FRAME_THUMBNAIL_SIZE: u32 = 256;
+const VIDEO_EXTENSIONS: &[&str] = &["mp4", "webm", "mkv", "mov", "avi", "m4v"];
+
+fn to_argb(rgba: Rgba<u8>) -> u32 {
+    ((rgba[3] as u32) << 24) | ((rgba[0] as u32) << 16) | ((rgba[1] as u32) << 8) | (rgba[2] as u32)
+}
+
+fn quantize_image(img: &DynamicImage) -> HashMap<u32, u32> {
     let (width, height) = img.dimensions();
 
     let mut pixels = Vec::with_capacity((width * height) as usize);
-    for pixel in img.pixels() {
-        let (_x, _y, rgba) = pixel;
-        let argb = ((rgba[3] as u32) << 24)
-            | ((rgba[0] as u32) << 16)
-            | ((rgba[1] as u32) << 8)
-            | (rgba[2] as u32);
-        pixels.push(Argb::from_u32(argb));
+    for (_x, _y, rgba) in img.pixels() {
+        pixels.push(Argb::from_u32(to_argb(rgba)));
     }
 
     let result = QuantizerCelebi::quantize(&pixels, 128);
-    let colors_to_population: HashMap<u32, u32> = result
+    result
         .color_to_count
         .iter()
         .map(|(k, v)| {
@@ -130,7 +139,276 @@ pub fn score_image(image_path: &str) -> Result<Hct> {
 
             (k_u32, *v)
         })
+        .collect()
+}
+
+fn merge_populations(frames: &[DynamicImage]) -> HashMap<u32, u32> {
+    let mut merged: HashMap<u32, u32> = HashMap::new();
+    for frame in frames {
+        for (argb, population) in quantize_image(frame) {
+            *merged.entry(argb).or_insert(0) += population;
+        }
+    }
+    merged
+}
+
+/// Picks `count` frames spread evenly across `frames`, always including the first.
+fn sample_evenly<T>(mut frames: Vec<T>, count: usize) -> Vec<T> {
+    if frames.len() <= count {
+        return frames;
+    }
+
+    let step = frames.len() as f64 / count as f64;
+    let wanted: Vec<usize> = (0..count).map(|i| (i as f64 * step) as usize).collect();
+
+    let mut idx = 0;
+    frames.retain(|_| {
+        let keep = wanted.contains(&idx);
+        idx += 1;
+        keep
+    });
+    frames
+}
+
+/// Keeps an evenly spread sample of a sequence of unknown length while it streams past,
+/// holding at most `2 * count` items. Every `stride`th item is kept; when the buffer fills
+/// up, every other kept item is dropped and the stride doubles.
+struct StreamSampler<T> {
+    count: usize,
+    stride: usize,
+    seen: usize,
+    kept: Vec<T>,
+}
+
+impl<T> StreamSampler<T> {
+    fn new(count: usize) -> Self {
+        StreamSampler {
+            count,
+            stride: 1,
+            seen: 0,
+            kept: Vec::with_capacity(count * 2),
+        }
+    }
+
+    /// Adds the next item, only calling `make` if it is kept.
+    fn push_with(&mut self, make: impl FnOnce() -> T) {
+        if self.seen.is_multiple_of(self.stride) {
+            self.kept.push(make());
+            if self.kept.len() >= self.count * 2 {
+                let mut idx = 0;
+                self.kept.retain(|_| {
+                    idx += 1;
+                    idx % 2 == 1
+                });
+                self.stride *= 2;
+            }
+        }
+        self.seen += 1;
+    }
+
+    fn finish(self) -> Vec<T> {
+        sample_evenly(self.kept, self.count)
+    }
+}
+
+fn decode_frames<'a>(decoder: impl AnimationDecoder<'a>) -> Result<Vec<DynamicImage>> {
+    // Frames are sampled as they are decoded and only the kept ones are shrunk, so long
+    // animations never sit in memory in full.
+    let mut sampler = StreamSampler::new(ANIMATION_SAMPLE_FRAMES);
+    for frame in decoder.into_frames() {
+        let buffer = frame?.into_buffer();
+        sampler.push_with(|| {
+            DynamicImage::ImageRgba8(buffer).thumbnail(FRAME_THUMBNAIL_SIZE, FRAME_THUMBNAIL_SIZE)
+        });
+    }
+    Ok(sampler.finish())
+}
+
+/// Returns the sampled frames of an animated GIF, APNG or WebP, or `None` for still images.
+fn animation_frames(path: &Path) -> Result<Option<Vec<DynamicImage>>> {
+    let guessed = ImageReader::new(BufReader::new(File::open(path)?)).with_guessed_format()?;
+    let format = guessed.format();
+    let reader = guessed.into_inner();
+
+    let frames = match format {
+        Some(ImageFormat::Gif) => decode_frames(GifDecoder::new(reader)?)?,
+        Some(ImageFormat::Png) => {
+            let decoder = PngDecoder::new(reader)?;
+            if !decoder.is_apng() {
+                return Ok(None);
+            }
+            decode_frames(decoder.apng())?
+        }
+        Some(ImageFormat::WebP) => {
+            let decoder = WebPDecoder::new(reader)?;
+            if !decoder.has_animation() {
+                return Ok(None);
+            }
+            decode_frames(decoder)?
+        }
+        _ => return Ok(None),
+    };
+
+    if frames.is_empty() {
+        bail!("No frames could be decoded from {}", path.display());
+    }
+    Ok(Some(frames))
+}
+
+pub fn is_video(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| VIDEO_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+fn video_duration(path: &Path) -> Option<f64> {
+    let output = Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-show_entries",
+            "format=duration",
+            "-of",
+            "csv=p=0",
+        ])
+        .arg(path)
+        .output()
+        .ok()?;
+
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// Extracts evenly spaced frames from a video wallpaper with ffmpeg.
+fn video_frames(path: &Path) -> Result<Vec<DynamicImage>> {
+    if which::which("ffmpeg").is_err() {
+        bail!("ffmpeg is required to extract colours from video wallpapers");
+    }
+
+    let interval = video_duration(path)
+        .filter(|d| *d > 0.0)
+        .map(|d| d / ANIMATION_SAMPLE_FRAMES as f64)
+        .unwrap_or(1.0);
+
+    let out_dir = tempfile::tempdir()?;
+    let filter = format!("fps=1/{:.3},scale={}:-2", interval, FRAME_THUMBNAIL_SIZE);
+
+    let output = Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(path)
+        .args(["-vf", &filter, "-frames:v"])
+        .arg(ANIMATION_SAMPLE_FRAMES.to_string())
+        .arg(out_dir.path().join("frame%03d.png"))
+        .output()
+        .context("Failed to run ffmpeg")?;
+
+    if !output.status.success() {
+        bail!(
+            "ffmpeg failed to extract frames: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let mut frame_paths: Vec<_> = fs::read_dir(out_dir.path())?
+        .filter_map(|e| e.ok().map(|e| e.path()))
         .collect();
+    frame_paths.sort();
+
+    let frames = frame_paths
+        .iter()
+        .map(image::open)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if frames.is_empty() {
+        bail!("ffmpeg produced no frames for {}", path.display());
+    }
+    Ok(frames)
+}
+
+pub fn score_image(image_path: &str) -> Result<Hct> {
+    let path = Path::new(image_path);
+
+    let colors_to_population = if is_video(path) {
+        merge_populations(&video_frames(path)?)
+    } else if let Some(frames) = animation_frames(path)? {
+        merge_populations(&frames)
+    } else {
+        quantize_image(&image::open(path)?)
+    };
 
     Ok(calculate_score(&colors_to_population, true))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::GifEncoder;
+    use image::{Frame, RgbaImage};
+
+    #[test]
+    fn test_sample_evenly_keeps_short_sequences() {
+        let frames = vec![1, 2, 3];
+        assert_eq!(sample_evenly(frames, 8), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_sample_evenly_spreads_across_sequence() {
+        let frames: Vec<usize> = (0..16).collect();
+        assert_eq!(sample_evenly(frames, 4), vec![0, 4, 8, 12]);
+    }
+
+    #[test]
+    fn test_stream_sampler_bounds_memory_and_spreads() {
+        let mut sampler = StreamSampler::new(4);
+        let mut made = 0;
+        for i in 0..100 {
+            sampler.push_with(|| {
+                made += 1;
+                i
+            });
+            assert!(sampler.kept.len() < 8);
+        }
+        assert!(made < 30);
+
+        let frames = sampler.finish();
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[0], 0);
+        assert!(frames[3] >= 50);
+    }
+
+    #[test]
+    fn test_score_animated_gif_merges_frames() {
+        let dir = tempfile::tempdir().unwrap();
+        let gif_path = dir.path().join("wall.gif");
+
+        {
+            let file = File::create(&gif_path).unwrap();
+            let mut encoder = GifEncoder::new(file);
+            let red = RgbaImage::from_pixel(16, 16, Rgba([220, 30, 30, 255]));
+            let blue = RgbaImage::from_pixel(16, 16, Rgba([30, 30, 220, 255]));
+            encoder
+                .encode_frames(vec![Frame::new(red), Frame::new(blue)])
+                .unwrap();
+        }
+
+        let frames = animation_frames(&gif_path)
+            .unwrap()
+            .expect("gif is animated");
+        assert_eq!(frames.len(), 2);
+
+        let populations = merge_populations(&frames);
+        assert!(populations.len() >= 2);
+
+        assert!(score_image(gif_path.to_str().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_still_png_is_not_animation() {
+        let dir = tempfile::tempdir().unwrap();
+        let png_path = dir.path().join("wall.png");
+        RgbaImage::from_pixel(8, 8, Rgba([10, 200, 10, 255]))
+            .save(&png_path)
+            .unwrap();
+
+        assert!(animation_frames(&png_path).unwrap().is_none());
+    }
+}
@@ -0,0 +1,378 @@
+use anyhow::{Context, Result, bail};
+use image::{DynamicImage, ImageFormat};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use tempfile::{Builder, NamedTempFile};
+
+use super::config::load_user_config;
+use super::gen_scheme::gen_scheme;
+use super::notify::{Category, Notification, NotifyConfig, Urgency, notify};
+use super::paths::{Paths, staged_dump, staged_file, staged_write};
+use super::score::{is_video, score_image};
+
+const MAX_HISTORY: usize = 50;
+const THUMBNAIL_SIZE: u32 = 128;
+const WALLPAPER_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "webp", "gif", "bmp", "tif", "tiff", "mp4", "webm", "mkv", "mov",
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub path: String,
+    #[serde(default)]
+    pub scheme: Value,
+}
+
+/// Bounded undo/redo log of wallpapers. `cursor` points at the entry currently applied.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct History {
+    pub entries: Vec<HistoryEntry>,
+    pub cursor: usize,
+}
+
+impl History {
+    pub fn load(paths: &Paths) -> Self {
+        fs::read_to_string(&paths.wallpaper_history_path)
+            .ok()
+            .and_then(|c| serde_json::from_str::<History>(&c).ok())
+            .map(|mut h| {
+                h.cursor = h.cursor.min(h.entries.len().saturating_sub(1));
+                h
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn current(&self) -> Option<&HistoryEntry> {
+        self.entries.get(self.cursor)
+    }
+
+    /// Refreshes the scheme recorded for the current entry, so leaving it keeps whatever
+    /// scheme the user switched to while it was active.
+    pub fn update_current_scheme(&mut self, scheme: Value) {
+        if let Some(entry) = self.entries.get_mut(self.cursor) {
+            entry.scheme = scheme;
+        }
+    }
+
+    /// Records a new entry after the cursor, dropping any redo entries and the oldest
+    /// entries beyond `MAX_HISTORY`.
+    pub fn push(&mut self, entry: HistoryEntry) {
+        if !self.entries.is_empty() {
+            self.entries.truncate(self.cursor + 1);
+        }
+        self.entries.push(entry);
+
+        if self.entries.len() > MAX_HISTORY {
+            let excess = self.entries.len() - MAX_HISTORY;
+            self.entries.drain(..excess);
+        }
+        self.cursor = self.entries.len() - 1;
+    }
+
+    pub fn previous(&mut self) -> Option<&HistoryEntry> {
+        if self.cursor == 0 || self.entries.is_empty() {
+            return None;
+        }
+        self.cursor -= 1;
+        self.current()
+    }
+
+    pub fn next(&mut self) -> Option<&HistoryEntry> {
+        if self.cursor + 1 >= self.entries.len() {
+            return None;
+        }
+        self.cursor += 1;
+        self.current()
+    }
+}
+
+pub fn get_wallpaper(paths: &Paths) -> Option<String> {
+    fs::read_to_string(&paths.wallpaper_path_path).ok()
+}
+
+fn read_scheme(paths: &Paths) -> Value {
+    fs::read_to_string(&paths.scheme_path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or(Value::Null)
+}
+
+fn is_wallpaper(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| WALLPAPER_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// Generates dynamic scheme colours for a wallpaper. With smart mode the light/dark mode
+/// follows the tone of the extracted primary colour instead of the current scheme.
+pub fn get_colours_for_wall(wall: &str, no_smart: bool, paths: &Paths) -> Result<Value> {
+    let scheme = read_scheme(paths);
+    let variant = scheme["variant"]
+        .as_str()
+        .unwrap_or("tonalspot")
+        .to_string();
+
+    let primary = score_image(wall)?;
+
+    let mode = if no_smart {
+        scheme["mode"].as_str().unwrap_or("dark").to_string()
+    } else if primary.get_tone() > 60.0 {
+        "light".to_string()
+    } else {
+        "dark".to_string()
+    };
+
+    let colours: HashMap<String, String> = gen_scheme(&variant, primary, mode == "dark");
+
+    Ok(json!({
+        "name": "dynamic",
+        "flavour": "default",
+        "mode": mode,
+        "variant": variant,
+        "colours": colours,
+    }))
+}
+
+/// A thumbnail of `wall` in a temporary file, or `None` for videos, which have none.
+fn staged_thumbnail(wall: &Path, paths: &Paths) -> Result<Option<NamedTempFile>> {
+    if is_video(wall) {
+        return Ok(None);
+    }
+
+    let img = image::open(wall)?;
+    let thumbnail =
+        DynamicImage::ImageRgb8(img.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgb8());
+    let file = staged_file(&paths.wallpaper_thumbnail_path, |f| {
+        thumbnail
+            .write_to(f, ImageFormat::Jpeg)
+            .map_err(io::Error::other)
+    })?;
+    Ok(Some(file))
+}
+
+/// Reports a dynamic scheme that could not be generated. The wallpaper is mostly set from
+/// the shell, where nobody sees stderr.
+fn notify_scheme_error(wall: &Path, error: &anyhow::Error, paths: &Paths) {
+    let user = load_user_config(paths);
+    // Best effort: the error is returned either way.
+    let _ = notify(
+        &Notification::new("Unable to set dynamic scheme")
+            .category(Category::SchemeErrors)
+            .urgency(Urgency::Critical)
+            .body(format!("{}: {}", wall.display(), error)),
+        &NotifyConfig::from_user(user.as_ref(), paths),
+    );
+}
+
+/// Points the wallpaper state (path file, link and thumbnail) at `wall`, replaces the scheme
+/// when there is one and saves `history`. Everything is written to temporary files first and
+/// only renamed into place once all of it is ready, so a failure leaves the old state alone.
+fn apply_wallpaper(
+    wall: &Path,
+    scheme: Option<&Value>,
+    history: &History,
+    paths: &Paths,
+) -> Result<()> {
+    let thumbnail = staged_thumbnail(wall, paths)?;
+    let link_dir = paths
+        .wallpaper_link_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(link_dir)?;
+    let link = Builder::new()
+        .make_in(link_dir, |path| symlink(wall, path))
+        .with_context(|| format!("Failed to link {}", paths.wallpaper_link_path.display()))?;
+    let path = staged_write(&paths.wallpaper_path_path, &wall.to_string_lossy())?;
+    let scheme = scheme
+        .map(|scheme| staged_dump(&paths.scheme_path, scheme))
+        .transpose()?;
+    let history = staged_dump(&paths.wallpaper_history_path, history)?;
+
+    match thumbnail {
+        Some(thumbnail) => {
+            thumbnail.persist(&paths.wallpaper_thumbnail_path)?;
+        }
+        None => {
+            let _ = fs::remove_file(&paths.wallpaper_thumbnail_path);
+        }
+    }
+    link.persist(&paths.wallpaper_link_path)?;
+    path.persist(&paths.wallpaper_path_path)?;
+    if let Some(scheme) = scheme {
+        scheme.persist(&paths.scheme_path)?;
+    }
+    history.persist(&paths.wallpaper_history_path)?;
+    Ok(())
+}
+
+pub fn set_wallpaper(wall: &str, no_smart: bool, paths: &Paths) -> Result<()> {
+    let wall = fs::canonicalize(wall).with_context(|| format!("{} does not exist", wall))?;
+    if !wall.is_file() || !is_wallpaper(&wall) {
+        bail!("{} is not a valid wallpaper", wall.display());
+    }
+
+    let mut history = History::load(paths);
+    let mut scheme = read_scheme(paths);
+
+    if history.entries.is_empty()
+        && let Some(current) = get_wallpaper(paths)
+    {
+        history.push(HistoryEntry {
+            path: current.trim().to_string(),
+            scheme: scheme.clone(),
+        });
+    }
+    history.update_current_scheme(scheme.clone());
+
+    // Colours are worked out before anything changes, so a wallpaper that can't be read
+    // leaves the current one and its scheme alone.
+    let dynamic = scheme["name"].as_str() == Some("dynamic");
+    if dynamic {
+        scheme = get_colours_for_wall(&wall.to_string_lossy(), no_smart, paths)
+            .inspect_err(|e| notify_scheme_error(&wall, e, paths))?;
+    }
+
+    history.push(HistoryEntry {
+        path: wall.to_string_lossy().into_owned(),
+        scheme: scheme.clone(),
+    });
+    apply_wallpaper(&wall, dynamic.then_some(&scheme), &history, paths)
+}
+
+fn collect_wallpapers(dir: &Path, out: &mut Vec<PathBuf>) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                collect_wallpapers(&path, out);
+            } else if is_wallpaper(&path) {
+                out.push(path);
+            }
+        }
+    }
+}
+
+/// Sets a wallpaper picked at random from `wallpapers_dir`, other than the current one. It
+/// goes into the history like any other, so `--previous` gets back to where you were.
+pub fn set_random(no_smart: bool, paths: &Paths) -> Result<()> {
+    let mut walls = Vec::new();
+    collect_wallpapers(&paths.wallpapers_dir, &mut walls);
+
+    let current = get_wallpaper(paths).map(|w| PathBuf::from(w.trim()));
+    walls.retain(|w| Some(w) != current.as_ref());
+
+    match walls.choose(&mut rand::thread_rng()) {
+        Some(wall) => set_wallpaper(&wall.to_string_lossy(), no_smart, paths),
+        None => bail!("No wallpapers found in {}", paths.wallpapers_dir.display()),
+    }
+}
+
+/// Whether `scheme` can be written back as the current scheme: nothing was recorded, or a
+/// scheme with a name and its colours.
+fn is_restorable(scheme: &Value) -> bool {
+    scheme.is_null() || (scheme["name"].is_string() && scheme["colours"].is_object())
+}
+
+/// Restores `entry`, saving `history`, which has already moved onto it.
+fn restore(entry: &HistoryEntry, history: &History, paths: &Paths) -> Result<()> {
+    let wall = Path::new(&entry.path);
+    if !wall.is_file() {
+        bail!("{} no longer exists", entry.path);
+    }
+    // Checked up front so a broken entry can't leave the image and scheme out of step.
+    if !is_restorable(&entry.scheme) {
+        bail!("The scheme recorded for {} is invalid", entry.path);
+    }
+
+    let scheme = (!entry.scheme.is_null()).then_some(&entry.scheme);
+    apply_wallpaper(wall, scheme, history, paths)
+}
+
+/// Moves through the history by one step and restores that wallpaper and its scheme.
+pub fn step_history(forward: bool, paths: &Paths) -> Result<()> {
+    let mut history = History::load(paths);
+    history.update_current_scheme(read_scheme(paths));
+
+    let entry = if forward {
+        history.next()
+    } else {
+        history.previous()
+    };
+
+    match entry.cloned() {
+        Some(entry) => restore(&entry, &history, paths),
+        None if forward => bail!("Already at the newest wallpaper"),
+        None => bail!("No previous wallpaper in history"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str) -> HistoryEntry {
+        HistoryEntry {
+            path: path.to_string(),
+            scheme: json!({ "name": path }),
+        }
+    }
+
+    #[test]
+    fn test_history_previous_and_next() {
+        let mut history = History::default();
+        history.push(entry("a"));
+        history.push(entry("b"));
+        history.push(entry("c"));
+
+        assert_eq!(history.previous().unwrap().path, "b");
+        assert_eq!(history.previous().unwrap().path, "a");
+        assert!(history.previous().is_none());
+        assert_eq!(history.next().unwrap().path, "b");
+        assert_eq!(history.next().unwrap().path, "c");
+        assert!(history.next().is_none());
+    }
+
+    #[test]
+    fn test_history_push_drops_redo_entries() {
+        let mut history = History::default();
+        history.push(entry("a"));
+        history.push(entry("b"));
+        history.previous();
+        history.push(entry("c"));
+
+        let paths: Vec<&str> = history.entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["a", "c"]);
+        assert_eq!(history.cursor, 1);
+    }
+
+    #[test]
+    fn test_is_restorable() {
+        assert!(is_restorable(&Value::Null));
+        assert!(is_restorable(
+            &json!({ "name": "dynamic", "colours": { "primary": "ffffff" } })
+        ));
+        assert!(!is_restorable(&json!({ "name": "dynamic" })));
+        assert!(!is_restorable(&json!("dynamic")));
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut history = History::default();
+        for i in 0..(MAX_HISTORY + 5) {
+            history.push(entry(&i.to_string()));
+        }
+
+        assert_eq!(history.entries.len(), MAX_HISTORY);
+        assert_eq!(history.entries[0].path, "5");
+        assert_eq!(
+            history.current().unwrap().path,
+            (MAX_HISTORY + 4).to_string()
+        );
+    }
+}
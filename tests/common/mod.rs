@@ -0,0 +1,336 @@
+//! A fake Hyprland instance for integration tests. It serves the request socket under a
+//! temporary `XDG_RUNTIME_DIR`, answers queries from scripted state and records every
+//! dispatch it receives, with `[[BATCH]]` requests split into their sub-commands.
+
+#![allow(dead_code)]
+
+pub mod notify;
+pub mod qs;
+
+use serde_json::{Value, json};
+use std::fs;
+use std::io::{Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+const SIGNATURE: &str = "ferret_test_instance";
+
+/// Puts executable scripts named after `tools` on a PATH in front of the system one.
+pub fn fake_tools(root: &Path, tools: &[(&str, &str)]) -> String {
+    let bin = root.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    for (name, script) in tools {
+        let path = bin.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    format!(
+        "{}:{}",
+        bin.display(),
+        std::env::var("PATH").unwrap_or_default()
+    )
+}
+
+/// What a ferret run wrote to stderr, for assertion messages.
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+/// The file a capture command printed as saved.
+pub fn saved_file(output: &Output) -> PathBuf {
+    PathBuf::from(stdout(output).trim())
+}
+
+/// A bus address nothing listens on, so ferret never reaches the real notification server.
+pub fn no_bus(root: &Path) -> String {
+    format!("unix:path={}", root.join("no-bus").display())
+}
+
+#[derive(Default)]
+struct State {
+    clients: Vec<Value>,
+    monitors: Vec<Value>,
+    workspaces: Vec<Value>,
+    /// Dispatch prefixes that are answered with an error instead of `ok`.
+    rejections: Vec<(String, String)>,
+    /// Clients that appear once an `exec` dispatch containing the key arrives.
+    spawns: Vec<(String, Value)>,
+    /// How long spawned clients take to appear.
+    spawn_delay: Duration,
+    /// Spawned clients that appear once their time comes.
+    starting: Vec<(Instant, Value)>,
+    requests: Vec<String>,
+    /// Payloads exactly as they arrived, one per connection.
+    payloads: Vec<String>,
+    /// Payload prefixes whose connection is closed without a reply, like during a reload.
+    hang_ups: Vec<String>,
+}
+
+pub struct MockHypr {
+    dir: TempDir,
+    state: Arc<Mutex<State>>,
+}
+
+pub fn client(address: &str, class: &str, workspace: &str) -> Value {
+    json!({
+        "address": address, "mapped": true, "hidden": false,
+        "at": [0, 0], "size": [800, 600],
+        "workspace": { "id": 1, "name": workspace },
+        "floating": false, "pseudo": false, "monitor": 0,
+        "class": class, "title": class, "initialClass": class, "initialTitle": class,
+        "pid": 1000, "xwayland": false, "pinned": false, "fullscreen": 0,
+        "fullscreenClient": 0, "grouped": [], "tags": [], "swallowing": "0x0",
+        "focusHistoryID": 0
+    })
+}
+
+pub fn monitor(id: i64, name: &str, focused: bool, special: &str) -> Value {
+    json!({
+        "id": id, "name": name, "description": name,
+        "width": 1920, "height": 1080, "refreshRate": 60.0, "x": 1920 * id, "y": 0,
+        "activeWorkspace": { "id": id + 1, "name": (id + 1).to_string() },
+        "specialWorkspace": { "id": if special.is_empty() { 0 } else { -98 }, "name": special },
+        "reserved": [0, 0, 0, 0], "scale": 1.0, "transform": 0,
+        "focused": focused, "disabled": false
+    })
+}
+
+pub fn workspace(id: i64, name: &str, monitor: &str) -> Value {
+    json!({
+        "id": id, "name": name, "monitor": monitor, "monitorID": 0, "windows": 1,
+        "hasfullscreen": false, "lastwindow": "0x0", "lastwindowtitle": ""
+    })
+}
+
+impl MockHypr {
+    pub fn new() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_dir = dir.path().join("hypr").join(SIGNATURE);
+        fs::create_dir_all(&socket_dir).unwrap();
+        fs::create_dir_all(dir.path().join("config/ferret")).unwrap();
+
+        let listener = UnixListener::bind(socket_dir.join(".socket.sock")).unwrap();
+        let state = Arc::new(Mutex::new(State {
+            monitors: vec![monitor(0, "DP-1", true, "")],
+            ..Default::default()
+        }));
+
+        let server_state = Arc::clone(&state);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                handle(stream, &server_state);
+            }
+        });
+
+        MockHypr { dir, state }
+    }
+
+    pub fn with_client(self, client: Value) -> Self {
+        self.state.lock().unwrap().clients.push(client);
+        self
+    }
+
+    pub fn with_monitors(self, monitors: Vec<Value>) -> Self {
+        self.state.lock().unwrap().monitors = monitors;
+        self
+    }
+
+    /// Replaces the monitors of a running instance, e.g. after a special workspace was hidden.
+    pub fn set_monitors(&self, monitors: Vec<Value>) {
+        self.state.lock().unwrap().monitors = monitors;
+    }
+
+    pub fn with_workspaces(self, workspaces: Vec<Value>) -> Self {
+        self.state.lock().unwrap().workspaces = workspaces;
+        self
+    }
+
+    pub fn rejecting(self, prefix: &str, reason: &str) -> Self {
+        self.state
+            .lock()
+            .unwrap()
+            .rejections
+            .push((prefix.to_string(), reason.to_string()));
+        self
+    }
+
+    /// Closes the connection without replying to payloads starting with `prefix`, after
+    /// handling them as usual.
+    pub fn hanging_up_on(self, prefix: &str) -> Self {
+        self.state.lock().unwrap().hang_ups.push(prefix.to_string());
+        self
+    }
+
+    /// Makes `client` appear when an `exec` dispatch containing `command` is received.
+    pub fn spawning(self, command: &str, client: Value) -> Self {
+        self.state
+            .lock()
+            .unwrap()
+            .spawns
+            .push((command.to_string(), client));
+        self
+    }
+
+    /// Makes clients from `spawning` appear only `delay` after their `exec` dispatch.
+    pub fn with_spawn_delay(self, delay: Duration) -> Self {
+        self.state.lock().unwrap().spawn_delay = delay;
+        self
+    }
+
+    /// Writes the `toggles` section of the user `cli.json`.
+    pub fn with_toggles(self, toggles: Value) -> Self {
+        self.with_user_config(json!({ "toggles": toggles }))
+    }
+
+    pub fn with_user_config(self, config: Value) -> Self {
+        fs::write(self.config_path(), config.to_string()).unwrap();
+        self
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        self.dir.path().join("config/ferret/cli.json")
+    }
+
+    pub fn root(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Every dispatch received so far, e.g. `dispatch togglespecialworkspace music`.
+    pub fn dispatches(&self) -> Vec<String> {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .filter(|r| r.starts_with("dispatch "))
+            .cloned()
+            .collect()
+    }
+
+    /// Every request received so far, including queries such as `j/clients`.
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Raw payloads, one per socket connection, so tests can tell batched from single requests.
+    pub fn payloads(&self) -> Vec<String> {
+        self.state.lock().unwrap().payloads.clone()
+    }
+
+    /// The ferret binary set up to run against this instance with an isolated config and
+    /// state.
+    pub fn command(&self, args: &[&str]) -> Command {
+        let root = self.dir.path();
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_ferret"));
+        cmd.args(args)
+            .env("XDG_RUNTIME_DIR", root)
+            .env("HYPRLAND_INSTANCE_SIGNATURE", SIGNATURE)
+            .env("HOME", root)
+            .env("XDG_CONFIG_HOME", root.join("config"))
+            .env("XDG_STATE_HOME", root.join("state"))
+            .env("XDG_CACHE_HOME", root.join("cache"))
+            .env("XDG_DATA_HOME", root.join("data"))
+            .env("DBUS_SESSION_BUS_ADDRESS", no_bus(root));
+        cmd
+    }
+
+    /// `subcommand` run against the tools [`fake_tools`] put on `path`, which record
+    /// their arguments in `$ARGS` and what they copy in `$CLIP`.
+    pub fn tool_command(&self, subcommand: &str, args: &[&str], path: &str) -> Command {
+        let root = self.dir.path();
+        let mut cmd = self.command(&[&[subcommand], args].concat());
+        cmd.env("PATH", path)
+            .env("ARGS", root.join("args"))
+            .env("CLIP", root.join("clip"));
+        cmd
+    }
+
+    pub fn ferret(&self, args: &[&str]) -> Output {
+        self.command(args).output().unwrap()
+    }
+}
+
+fn reply(request: &str, state: &mut State) -> String {
+    state.requests.push(request.to_string());
+
+    let now = Instant::now();
+    let (started, starting) = state.starting.drain(..).partition(|(at, _)| *at <= now);
+    state.starting = starting;
+    state.clients.extend(
+        started
+            .into_iter()
+            .map(|(_, client): (Instant, Value)| client),
+    );
+
+    match request {
+        "j/clients" => Value::Array(state.clients.clone()).to_string(),
+        "j/monitors" => Value::Array(state.monitors.clone()).to_string(),
+        "j/workspaces" => Value::Array(state.workspaces.clone()).to_string(),
+        "j/activewindow" => state
+            .clients
+            .iter()
+            .find(|c| c["focusHistoryID"] == 0)
+            .cloned()
+            .unwrap_or_else(|| json!({}))
+            .to_string(),
+        r if r.starts_with("dispatch exec ") => {
+            let at = Instant::now() + state.spawn_delay;
+            let spawned: Vec<(Instant, Value)> = state
+                .spawns
+                .iter()
+                .filter(|(command, _)| r.contains(command.as_str()))
+                .map(|(_, client)| (at, client.clone()))
+                .collect();
+            state.starting.extend(spawned);
+            "ok".to_string()
+        }
+        r if r.starts_with("dispatch ") => state
+            .rejections
+            .iter()
+            .find(|(prefix, _)| r.starts_with(prefix.as_str()))
+            .map(|(_, reason)| reason.clone())
+            .unwrap_or_else(|| "ok".to_string()),
+        _ => "unknown request".to_string(),
+    }
+}
+
+fn handle(mut stream: UnixStream, state: &Mutex<State>) {
+    let mut buf = vec![0u8; 65536];
+    let n = match stream.read(&mut buf) {
+        Ok(n) => n,
+        Err(_) => return,
+    };
+    let request = String::from_utf8_lossy(&buf[..n]).to_string();
+
+    let mut state = state.lock().unwrap();
+    state.payloads.push(request.clone());
+    let response = match request.strip_prefix("[[BATCH]]") {
+        Some(cmds) => cmds
+            .split(';')
+            .map(|c| reply(c.trim(), &mut state))
+            .collect::<Vec<_>>()
+            .join("\n\n\n"),
+        None => reply(&request, &mut state),
+    };
+    let hang_up = state
+        .hang_ups
+        .iter()
+        .any(|p| request.starts_with(p.as_str()));
+    drop(state);
+
+    if hang_up {
+        return;
+    }
+
+    let _ = stream.write_all(response.as_bytes());
+}
@@ -0,0 +1,227 @@
+//! A stand-in notification server on a private `dbus-daemon`. It records every `Notify`
+//! call and, when told to, clicks an action on the notifications that offer it.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::OwnedValue;
+
+const OBJECT_PATH: &str = "/org/freedesktop/Notifications";
+const INTERFACE: &str = "org.freedesktop.Notifications";
+
+/// A `Notify` call as the server received it.
+#[derive(Debug, Clone)]
+pub struct Sent {
+    pub id: u32,
+    pub app: String,
+    pub replaces: u32,
+    pub icon: String,
+    pub summary: String,
+    pub body: String,
+    /// Flattened `[key, label, …]` pairs.
+    pub actions: Vec<String>,
+    pub urgency: Option<u8>,
+    pub value: Option<i32>,
+    pub timeout: i32,
+}
+
+#[derive(Default)]
+struct State {
+    sent: Vec<Sent>,
+    closed: Vec<u32>,
+    last_id: u32,
+    /// Action invoked on every notification that offers it.
+    click: Option<String>,
+}
+
+struct Server {
+    state: Arc<Mutex<State>>,
+}
+
+#[zbus::interface(name = "org.freedesktop.Notifications")]
+impl Server {
+    #[allow(clippy::too_many_arguments)]
+    async fn notify(
+        &self,
+        app_name: String,
+        replaces_id: u32,
+        app_icon: String,
+        summary: String,
+        body: String,
+        actions: Vec<String>,
+        hints: HashMap<String, OwnedValue>,
+        expire_timeout: i32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> u32 {
+        let (id, click) = {
+            let mut state = self.state.lock().unwrap();
+            let id = match replaces_id {
+                0 => {
+                    state.last_id += 1;
+                    state.last_id
+                }
+                id => id,
+            };
+            let click = state
+                .click
+                .clone()
+                .filter(|key| actions.iter().step_by(2).any(|a| a == key));
+
+            state.sent.push(Sent {
+                id,
+                app: app_name,
+                replaces: replaces_id,
+                icon: app_icon,
+                summary,
+                body,
+                actions,
+                urgency: hints.get("urgency").and_then(|v| v.downcast_ref().ok()),
+                value: hints.get("value").and_then(|v| v.downcast_ref().ok()),
+                timeout: expire_timeout,
+            });
+            (id, click)
+        };
+
+        if let Some(key) = click {
+            // Like a person, click a little after the notification shows up.
+            let connection = zbus::blocking::Connection::from(emitter.connection().clone());
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                let _ = connection.emit_signal(
+                    None::<&str>,
+                    OBJECT_PATH,
+                    INTERFACE,
+                    "ActionInvoked",
+                    &(id, key),
+                );
+            });
+        }
+        id
+    }
+
+    async fn close_notification(
+        &self,
+        id: u32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) {
+        self.state.lock().unwrap().closed.push(id);
+        let _ = emitter
+            .emit(INTERFACE, "NotificationClosed", &(id, 3u32))
+            .await;
+    }
+
+    fn get_capabilities(&self) -> Vec<String> {
+        vec!["actions".to_string(), "body".to_string()]
+    }
+
+    fn get_server_information(&self) -> (String, String, String, String) {
+        (
+            "stand-in".to_string(),
+            "ferret".to_string(),
+            "1.0".to_string(),
+            "1.2".to_string(),
+        )
+    }
+}
+
+pub struct NotificationDaemon {
+    _dir: TempDir,
+    bus: Child,
+    address: String,
+    state: Arc<Mutex<State>>,
+    _connection: zbus::blocking::Connection,
+}
+
+impl NotificationDaemon {
+    /// Starts a private bus with the server on it. Panics when `dbus-daemon` is not
+    /// installed, rather than letting notification tests pass without checking anything.
+    pub fn start() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("bus.conf");
+        fs::write(
+            &config,
+            format!(
+                "<busconfig>\
+                   <type>session</type>\
+                   <listen>unix:path={}</listen>\
+                   <auth>EXTERNAL</auth>\
+                   <policy context=\"default\">\
+                     <allow send_destination=\"*\" eavesdrop=\"true\"/>\
+                     <allow eavesdrop=\"true\"/>\
+                     <allow own=\"*\"/>\
+                   </policy>\
+                 </busconfig>",
+                dir.path().join("bus").display()
+            ),
+        )
+        .unwrap();
+
+        let mut bus = Command::new("dbus-daemon")
+            .arg(format!("--config-file={}", config.display()))
+            .args(["--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("dbus-daemon is needed for the notification tests");
+
+        // The address is printed once the bus accepts connections.
+        let mut address = String::new();
+        BufReader::new(bus.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+        let address = address.trim().to_string();
+
+        let state = Arc::new(Mutex::new(State::default()));
+        let server = Server {
+            state: Arc::clone(&state),
+        };
+        let connection = zbus::blocking::connection::Builder::address(address.as_str())
+            .unwrap()
+            .name(INTERFACE)
+            .unwrap()
+            .serve_at(OBJECT_PATH, server)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        NotificationDaemon {
+            _dir: dir,
+            bus,
+            address,
+            state,
+            _connection: connection,
+        }
+    }
+
+    /// The bus address to hand to ferret as `DBUS_SESSION_BUS_ADDRESS`.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Invokes `key` on every notification offering it.
+    pub fn clicking(self, key: &str) -> Self {
+        self.state.lock().unwrap().click = Some(key.to_string());
+        self
+    }
+
+    pub fn sent(&self) -> Vec<Sent> {
+        self.state.lock().unwrap().sent.clone()
+    }
+
+    pub fn closed(&self) -> Vec<u32> {
+        self.state.lock().unwrap().closed.clone()
+    }
+}
+
+impl Drop for NotificationDaemon {
+    fn drop(&mut self) {
+        let _ = self.bus.kill();
+        let _ = self.bus.wait();
+    }
+}
@@ -0,0 +1,212 @@
+//! A fake Quickshell IPC socket. It decodes the `QDataStream` commands ferret sends, answers
+//! calls from a scripted table and records each call as `target.function(args)`.
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tempfile::TempDir;
+
+/// A scripted reply: a variant index and an optional string payload.
+#[derive(Clone)]
+pub struct Reply(u32, Option<String>);
+
+pub fn returns(value: &str) -> Reply {
+    Reply(5, Some(value.to_string()))
+}
+
+pub fn void() -> Reply {
+    Reply(0, None)
+}
+
+pub fn invalid(reason: &str) -> Reply {
+    Reply(4, Some(reason.to_string()))
+}
+
+/// A target as `(name, [(function, return type, [(arg, type)])])`.
+pub type TargetSpec = (
+    &'static str,
+    Vec<(
+        &'static str,
+        &'static str,
+        Vec<(&'static str, &'static str)>,
+    )>,
+);
+
+#[derive(Default)]
+struct State {
+    targets: Vec<TargetSpec>,
+    replies: HashMap<String, Reply>,
+    calls: Vec<String>,
+    killed: bool,
+    /// Answer every command with a response type ferret doesn't know, like a Quickshell
+    /// release with a different protocol.
+    garbled: bool,
+}
+
+pub struct MockShell {
+    dir: TempDir,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockShell {
+    pub fn new() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let listener = UnixListener::bind(dir.path().join("ipc.sock")).unwrap();
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let server_state = Arc::clone(&state);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = handle(stream, &server_state);
+            }
+        });
+
+        MockShell { dir, state }
+    }
+
+    pub fn with_target(self, target: TargetSpec) -> Self {
+        self.state.lock().unwrap().targets.push(target);
+        self
+    }
+
+    /// Scripts the reply to `target.function`; unscripted calls report a missing target.
+    pub fn replying(self, call: &str, reply: Reply) -> Self {
+        self.state
+            .lock()
+            .unwrap()
+            .replies
+            .insert(call.to_string(), reply);
+        self
+    }
+
+    pub fn garbled(self) -> Self {
+        self.state.lock().unwrap().garbled = true;
+        self
+    }
+
+    pub fn socket_path(&self) -> PathBuf {
+        self.dir.path().join("ipc.sock")
+    }
+
+    pub fn root(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn calls(&self) -> Vec<String> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    pub fn killed(&self) -> bool {
+        self.state.lock().unwrap().killed
+    }
+
+    /// The ferret binary set up to run against this socket with an isolated config and state.
+    pub fn command(&self, args: &[&str]) -> Command {
+        let root = self.dir.path();
+        let mut cmd = Command::new(env!("CARGO_BIN_EXE_ferret"));
+        cmd.args(args)
+            .env("FERRET_QS_SOCKET", self.socket_path())
+            .env("XDG_RUNTIME_DIR", root)
+            .env("HOME", root)
+            .env("XDG_CONFIG_HOME", root.join("config"))
+            .env("XDG_STATE_HOME", root.join("state"))
+            .env("XDG_CACHE_HOME", root.join("cache"))
+            .env("XDG_DATA_HOME", root.join("data"))
+            .env("DBUS_SESSION_BUS_ADDRESS", super::no_bus(root));
+        cmd
+    }
+
+    pub fn ferret(&self, args: &[&str]) -> Output {
+        self.command(args).output().unwrap()
+    }
+}
+
+fn read_u32(stream: &mut UnixStream) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    stream.read_exact(&mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+}
+
+fn read_string(stream: &mut UnixStream) -> io::Result<String> {
+    let len = read_u32(stream)?;
+    let mut buf = vec![0u8; len as usize];
+    stream.read_exact(&mut buf)?;
+    let units: Vec<u16> = buf
+        .chunks_exact(2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+        .collect();
+    Ok(String::from_utf16_lossy(&units))
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend(value.to_be_bytes());
+}
+
+fn put_string(out: &mut Vec<u8>, value: &str) {
+    let utf16: Vec<u8> = value.encode_utf16().flat_map(u16::to_be_bytes).collect();
+    put_u32(out, utf16.len() as u32);
+    out.extend(utf16);
+}
+
+fn handle(mut stream: UnixStream, state: &Mutex<State>) -> io::Result<()> {
+    let mut out = Vec::new();
+
+    if state.lock().unwrap().garbled {
+        read_u32(&mut stream)?;
+        put_u32(&mut out, 99);
+        return stream.write_all(&out);
+    }
+
+    match read_u32(&mut stream)? {
+        1 => state.lock().unwrap().killed = true,
+        2 => {
+            read_string(&mut stream)?;
+            read_string(&mut stream)?;
+            let state = state.lock().unwrap();
+
+            put_u32(&mut out, 5);
+            put_u32(&mut out, state.targets.len() as u32);
+            for (name, functions) in &state.targets {
+                put_string(&mut out, name);
+                put_u32(&mut out, functions.len() as u32);
+                for (function, ret, args) in functions {
+                    put_string(&mut out, function);
+                    put_string(&mut out, ret);
+                    put_u32(&mut out, args.len() as u32);
+                    for (arg, ty) in args {
+                        put_string(&mut out, arg);
+                        put_string(&mut out, ty);
+                    }
+                }
+                put_u32(&mut out, 0);
+            }
+        }
+        3 => {
+            let target = read_string(&mut stream)?;
+            let function = read_string(&mut stream)?;
+            let count = read_u32(&mut stream)?;
+            let args = (0..count)
+                .map(|_| read_string(&mut stream))
+                .collect::<io::Result<Vec<_>>>()?;
+
+            let mut state = state.lock().unwrap();
+            state
+                .calls
+                .push(format!("{}.{}({})", target, function, args.join(", ")));
+
+            let key = format!("{}.{}", target, function);
+            let Reply(index, payload) = state.replies.get(&key).cloned().unwrap_or(Reply(2, None));
+            put_u32(&mut out, index);
+            if let Some(payload) = payload {
+                put_string(&mut out, &payload);
+            }
+        }
+        _ => {}
+    }
+
+    stream.write_all(&out)
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use common::MockHypr;
+use serde_json::json;
+
+#[test]
+fn validate_reports_typo_with_path() {
+    let hypr = MockHypr::new().with_toggles(json!({
+        "music": { "spotify": { "enable": true, "comand": ["spotify"] } }
+    }));
+
+    let output = hypr.ferret(&["config", "validate"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(!output.status.success());
+    assert!(stdout.contains("cli.json: /toggles/music/spotify:"));
+    assert!(stdout.contains("'comand' was unexpected"));
+}
+
+#[test]
+fn validate_accepts_valid_config() {
+    let hypr = MockHypr::new().with_toggles(json!({
+        "music": { "spotify": { "enable": true, "command": ["spotify"] } }
+    }));
+
+    let output = hypr.ferret(&["config", "validate"]);
+
+    assert!(output.status.success());
+}
+
+#[test]
+fn validate_accepts_wallpaper_and_theme_sections() {
+    let hypr = MockHypr::new().with_user_config(json!({
+        "wallpaper": { "postHook": "notify-send 'wallpaper changed'" },
+        "theme": { "enableGtk": false, "enableQt": true }
+    }));
+
+    let output = hypr.ferret(&["config", "validate"]);
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    let hypr = MockHypr::new().with_user_config(json!({ "theme": { "gtk": false } }));
+    let output = hypr.ferret(&["config", "validate"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("cli.json: /theme"));
+}
+
+#[test]
+fn toggle_warns_about_invalid_config() {
+    let hypr = MockHypr::new().with_toggles(json!({
+        "scratch": { "notes": { "enable": "yes", "match": [{"class": "notes"}] } }
+    }));
+
+    let output = hypr.ferret(&["toggle", "scratch"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success());
+    assert!(stderr.contains("warning:"));
+    assert!(stderr.contains("/toggles/scratch/notes/enable"));
+    assert!(stderr.contains("ignoring toggle"));
+}
+
+#[test]
+fn toggle_ignores_rule_with_invalid_regex() {
+    let hypr = MockHypr::new().with_toggles(json!({
+        "scratch": { "notes": { "enable": true, "match": [{"class": {"regex": "("}}] } }
+    }));
+
+    let output = hypr.ferret(&["toggle", "scratch"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.success());
+    assert!(stderr.contains("/class/regex: invalid regex"));
+    assert!(stderr.contains("ignoring toggle"));
+}
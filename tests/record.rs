@@ -0,0 +1,230 @@
+mod common;
+
+use common::notify::NotificationDaemon;
+use common::{MockHypr, fake_tools, monitor, saved_file, stderr};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+/// A fake wl-screenrec that appends its arguments to `$ARGS`, writes `partial` to its
+/// `-f` file and `mp4` once it is interrupted, and a slurp that picks a fixed region.
+fn recorder_tools(root: &Path) -> String {
+    fake_tools(
+        root,
+        &[
+            ("slurp", "echo '10,20 300x200'"),
+            (
+                "wl-screenrec",
+                "echo \"$@\" >> \"$ARGS\"\n\
+             while [ $# -gt 0 ]; do [ \"$1\" = -f ] && out=$2; shift; done\n\
+             trap 'printf mp4 > \"$out\"; exit 0' INT\n\
+             printf partial > \"$out\"\n\
+             while :; do sleep 0.05; done",
+            ),
+        ],
+    )
+}
+
+fn recording_path(root: &Path) -> PathBuf {
+    root.join("state/ferret/record/recording.mp4")
+}
+
+#[test]
+fn start_and_stop_saves_recording() {
+    let hypr = MockHypr::new().with_monitors(vec![
+        monitor(0, "DP-1", false, ""),
+        monitor(1, "DP-2", true, ""),
+    ]);
+    let root = hypr.root();
+    let path = recorder_tools(root);
+
+    let output = hypr
+        .tool_command("record", &["start", "--audio"], &path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        fs::read_to_string(root.join("args")).unwrap().trim(),
+        format!("-f {} -o DP-2 --audio", recording_path(root).display())
+    );
+
+    let output = hypr
+        .tool_command("record", &["status"], &path)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("recording (pid "));
+
+    let output = hypr
+        .tool_command("record", &["start"], &path)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("already recording"));
+
+    let output = hypr
+        .tool_command("record", &["stop"], &path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    let file = saved_file(&output);
+    assert!(file.starts_with(root.join("Videos/Recordings")));
+    assert!(
+        file.file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("recording_")
+    );
+    assert_eq!(fs::read_to_string(&file).unwrap(), "mp4");
+    assert!(!recording_path(root).exists());
+
+    let output = hypr
+        .tool_command("record", &["status"], &path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "not recording"
+    );
+}
+
+#[test]
+fn toggle_records_selected_region() {
+    let hypr = MockHypr::new();
+    let root = hypr.root();
+    let path = recorder_tools(root);
+
+    let output = hypr
+        .tool_command("record", &["toggle", "--region"], &path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        fs::read_to_string(root.join("args")).unwrap().trim(),
+        format!("-f {} -g 10,20 300x200", recording_path(root).display())
+    );
+
+    let output = hypr
+        .tool_command("record", &["toggle", "--region"], &path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(saved_file(&output).exists());
+}
+
+#[test]
+fn stop_without_recording_fails() {
+    let hypr = MockHypr::new();
+    let path = recorder_tools(hypr.root());
+
+    let output = hypr
+        .tool_command("record", &["stop"], &path)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("not recording"));
+}
+
+#[test]
+fn recording_notification_is_closed_on_stop() {
+    let daemon = NotificationDaemon::start();
+    let hypr = MockHypr::new().with_monitors(vec![monitor(0, "DP-1", true, "")]);
+    let root = hypr.root();
+    let path = recorder_tools(root);
+    let notif_path = root.join("state/ferret/record/notifid.txt");
+
+    let output = hypr
+        .tool_command("record", &["start"], &path)
+        .env("DBUS_SESSION_BUS_ADDRESS", daemon.address())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let sent = daemon.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].summary, "Recording started");
+    assert_eq!(sent[0].body, "Recording DP-1");
+    assert_eq!(sent[0].timeout, 0);
+    assert_eq!(
+        fs::read_to_string(&notif_path).unwrap(),
+        sent[0].id.to_string()
+    );
+
+    let output = hypr
+        .tool_command("record", &["stop"], &path)
+        .env("DBUS_SESSION_BUS_ADDRESS", daemon.address())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    assert_eq!(daemon.closed(), vec![sent[0].id]);
+    assert!(!notif_path.exists());
+    let sent = daemon.sent();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[1].summary, "Recording saved");
+    assert_eq!(sent[1].body, saved_file(&output).display().to_string());
+}
+
+#[test]
+fn concurrent_toggles_start_one_recorder() {
+    let hypr = MockHypr::new().with_monitors(vec![monitor(0, "DP-1", true, "")]);
+    let root = hypr.root();
+    let path = recorder_tools(root);
+
+    let toggle = || {
+        hypr.tool_command("record", &["toggle"], &path)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap()
+    };
+    let (first, second) = (toggle(), toggle());
+    let first = first.wait_with_output().unwrap();
+    let second = second.wait_with_output().unwrap();
+    assert!(first.status.success(), "{}", stderr(&first));
+    assert!(second.status.success(), "{}", stderr(&second));
+
+    // One of them started the recorder and the other stopped it again.
+    assert_eq!(
+        fs::read_to_string(root.join("args"))
+            .unwrap()
+            .lines()
+            .count(),
+        1
+    );
+    let saved: Vec<PathBuf> = [&first, &second]
+        .into_iter()
+        .map(saved_file)
+        .filter(|file| !file.as_os_str().is_empty())
+        .collect();
+    assert_eq!(saved.len(), 1);
+    assert_eq!(fs::read_to_string(&saved[0]).unwrap(), "mp4");
+}
+
+#[test]
+fn recorder_failing_to_start_reports_its_error() {
+    let hypr = MockHypr::new().with_monitors(vec![monitor(0, "DP-1", true, "")]);
+    let path = fake_tools(
+        hypr.root(),
+        &[(
+            "wl-screenrec",
+            "echo 'starting' >&2\necho 'no output named DP-1' >&2\nexit 1",
+        )],
+    );
+
+    let output = hypr
+        .tool_command("record", &["start"], &path)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output)
+            .contains("recorder exited straight away (exit status: 1): no output named DP-1"),
+        "{}",
+        stderr(&output)
+    );
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use common::notify::NotificationDaemon;
+use common::{MockHypr, client, fake_tools, monitor, saved_file, stderr};
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Fake grim, slurp and wl-copy. grim records its arguments in `$ARGS` and writes `png` to
+/// the file it is given; wl-copy saves what it is sent to `$CLIP`.
+fn screenshot_tools(root: &Path, slurp: &str) -> String {
+    fake_tools(
+        root,
+        &[
+            (
+                "grim",
+                "echo \"$@\" > \"$ARGS\"\nfor last; do :; done\nprintf png > \"$last\"",
+            ),
+            ("slurp", slurp),
+            ("wl-copy", "cat > \"$CLIP\""),
+        ],
+    )
+}
+
+#[test]
+fn window_mode_captures_active_window_and_copies_it() {
+    let mut window = client("0x1", "kitty", "1");
+    window["at"] = json!([100, 50]);
+    let hypr = MockHypr::new().with_client(window);
+    let root = hypr.root();
+    let path = screenshot_tools(root, "exit 1");
+
+    let output = hypr
+        .tool_command("screenshot", &["window", "--no-notify"], &path)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let file = saved_file(&output);
+    assert!(file.starts_with(root.join("Pictures/Screenshots")));
+    assert!(
+        file.file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("screenshot_")
+    );
+    assert_eq!(
+        fs::read_to_string(root.join("args")).unwrap().trim(),
+        format!("-g 100,50 800x600 {}", file.display())
+    );
+    assert_eq!(fs::read_to_string(&file).unwrap(), "png");
+    assert_eq!(fs::read_to_string(root.join("clip")).unwrap(), "png");
+}
+
+#[test]
+fn cancelled_region_selection_takes_nothing() {
+    let hypr = MockHypr::new();
+    let root = hypr.root();
+    let path = screenshot_tools(root, "exit 1");
+
+    let output = hypr
+        .tool_command("screenshot", &["region"], &path)
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+    assert!(!root.join("args").exists());
+}
+
+#[test]
+fn monitor_mode_without_saving_or_copying() {
+    let hypr = MockHypr::new().with_monitors(vec![
+        monitor(0, "DP-1", false, ""),
+        monitor(1, "DP-2", true, ""),
+    ]);
+    let root = hypr.root();
+    let path = screenshot_tools(root, "exit 1");
+
+    let output = hypr
+        .tool_command(
+            "screenshot",
+            &["monitor", "--no-save", "--no-copy", "--no-notify"],
+            &path,
+        )
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let file = saved_file(&output);
+    assert!(file.starts_with(root.join("cache/ferret/screenshots")));
+    assert!(
+        fs::read_to_string(root.join("args"))
+            .unwrap()
+            .starts_with("-o DP-2 ")
+    );
+    assert!(!root.join("clip").exists());
+}
+
+#[test]
+fn notification_delete_button_removes_screenshot() {
+    let daemon = NotificationDaemon::start().clicking("delete");
+    let hypr = MockHypr::new();
+    let path = screenshot_tools(hypr.root(), "echo '10,20 300x200'");
+
+    let output = hypr
+        .tool_command("screenshot", &[], &path)
+        .env("DBUS_SESSION_BUS_ADDRESS", daemon.address())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    let file = saved_file(&output);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while file.exists() {
+        assert!(Instant::now() < deadline, "screenshot was not deleted");
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    let sent = daemon.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].summary, "Screenshot taken");
+    assert_eq!(sent[0].icon, format!("file://{}", file.display()));
+    assert_eq!(sent[0].timeout, 5 * 60 * 1000);
+    assert_eq!(
+        sent[0].actions,
+        vec![
+            "default", "", "open", "Open", "edit", "Edit", "delete", "Delete"
+        ]
+    );
+}
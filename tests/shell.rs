@@ -0,0 +1,589 @@
+mod common;
+
+use common::notify::NotificationDaemon;
+use common::qs::{MockShell, invalid, returns, void};
+use common::{fake_tools, stderr, stdout};
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn shell() -> MockShell {
+    MockShell::new()
+        .with_target((
+            "wallpaper",
+            vec![
+                ("get", "string", vec![]),
+                ("set", "void", vec![("path", "string")]),
+            ],
+        ))
+        .with_target((
+            "drawers",
+            vec![("toggle", "void", vec![("drawer", "string")])],
+        ))
+}
+
+#[test]
+fn ipc_call_prints_return_value() {
+    let qs = shell().replying("wallpaper.get", returns("/walls/forest.png"));
+
+    let output = qs.ferret(&["shell", "wallpaper", "get"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "/walls/forest.png\n");
+    assert_eq!(qs.calls(), vec!["wallpaper.get()"]);
+}
+
+#[test]
+fn ipc_call_subcommand_passes_arguments() {
+    let qs = shell().replying("wallpaper.set", void());
+
+    let output = qs.ferret(&["shell", "ipc", "call", "wallpaper", "set", "/walls/a b.png"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+    assert_eq!(qs.calls(), vec!["wallpaper.set(/walls/a b.png)"]);
+}
+
+#[test]
+fn ipc_call_reports_typed_errors() {
+    let qs = shell().replying("wallpaper.set", invalid("expected 1 argument, got 0"));
+
+    let missing = qs.ferret(&["shell", "launcher", "open"]);
+    assert!(!missing.status.success());
+    assert!(stderr(&missing).contains("no IPC target named launcher"));
+
+    let rejected = qs.ferret(&["shell", "wallpaper", "set"]);
+    assert!(!rejected.status.success());
+    assert!(stderr(&rejected).contains("wallpaper.set rejected the call: expected 1 argument"));
+}
+
+#[test]
+fn ipc_list_prints_signatures() {
+    let qs = shell();
+
+    let output = qs.ferret(&["shell", "ipc", "list"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "target wallpaper\n  function get(): string\n  function set(path: string): void\n\
+         target drawers\n  function toggle(drawer: string): void\n"
+    );
+}
+
+#[test]
+fn ipc_list_json() {
+    let qs = shell();
+
+    let output = qs.ferret(&["shell", "ipc", "list", "--json"]);
+    let targets: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(targets[0]["name"], "wallpaper");
+    assert_eq!(targets[0]["functions"][1]["arguments"][0]["type"], "string");
+    assert_eq!(targets[1]["functions"][0]["returnType"], "void");
+}
+
+/// A fake `qs` that records its arguments in `$ARGS` and prints `output`.
+fn fake_qs(root: &Path, output: &str) -> String {
+    fake_tools(
+        root,
+        &[(
+            "qs",
+            &format!("echo \"$@\" > \"$ARGS\"\nprintf '{}'", output),
+        )],
+    )
+}
+
+#[test]
+fn ipc_call_falls_back_to_qs_when_socket_is_unusable() {
+    let qs = shell();
+    let path = fake_qs(qs.root(), "/walls/forest.png\\n");
+    // Longer than a socket address can hold, so connecting fails before anything is sent.
+    let socket = qs.root().join("x".repeat(120)).join("ipc.sock");
+
+    let output = qs
+        .command(&["shell", "wallpaper", "get"])
+        .env("FERRET_QS_SOCKET", socket)
+        .env("PATH", path)
+        .env("ARGS", qs.root().join("args"))
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "/walls/forest.png\n");
+    assert_eq!(
+        fs::read_to_string(qs.root().join("args")).unwrap(),
+        "-c ferret ipc call wallpaper get\n"
+    );
+}
+
+#[test]
+fn ipc_call_is_not_repeated_after_undecodable_reply() {
+    let qs = shell().garbled();
+    let path = fake_qs(qs.root(), "/walls/forest.png\\n");
+
+    let output = qs
+        .command(&["shell", "wallpaper", "set", "/walls/forest.png"])
+        .env("PATH", path)
+        .env("ARGS", qs.root().join("args"))
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("unknown response type 99"));
+    assert!(!qs.root().join("args").exists());
+}
+
+#[test]
+fn ipc_list_falls_back_to_qs_show() {
+    let qs = shell().garbled();
+    let path = fake_qs(
+        qs.root(),
+        "target drawers\\n  function toggle(drawer: string): void\\n",
+    );
+
+    let output = qs
+        .command(&["shell", "ipc", "list", "--json"])
+        .env("PATH", path)
+        .env("ARGS", qs.root().join("args"))
+        .output()
+        .unwrap();
+    let targets: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(targets[0]["name"], "drawers");
+    assert_eq!(targets[0]["functions"][0]["arguments"][0]["name"], "drawer");
+}
+
+#[test]
+fn target_named_ipc_can_be_called_after_double_dash() {
+    let qs = shell().replying("ipc.ping", returns("pong"));
+
+    let output = qs.ferret(&["shell", "--", "ipc", "ping"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "pong\n");
+    assert_eq!(qs.calls(), vec!["ipc.ping()"]);
+}
+
+#[test]
+fn kill_uses_ipc() {
+    let qs = shell();
+
+    let output = qs.ferret(&["shell", "--kill"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(qs.killed());
+}
+
+fn write_config(root: &Path, config: serde_json::Value) {
+    fs::create_dir_all(root.join("config/ferret")).unwrap();
+    fs::write(root.join("config/ferret/cli.json"), config.to_string()).unwrap();
+}
+
+fn supervisor_state(root: &Path) -> serde_json::Value {
+    let content = fs::read_to_string(root.join("state/ferret/shell/supervisor.json")).unwrap();
+    serde_json::from_str(&content).unwrap()
+}
+
+#[test]
+fn supervise_restarts_crashed_shell_and_records_crashes() {
+    let qs = MockShell::new();
+    let root = qs.root();
+    write_config(
+        root,
+        json!({ "shell": { "supervise": { "initialBackoff": 0.01, "notifyAfter": 2 } } }),
+    );
+    let path = fake_tools(
+        root,
+        &[(
+            "qs",
+            "n=$(cat \"$RUNS\" 2>/dev/null || echo 0); n=$((n + 1)); echo $n > \"$RUNS\"\n\
+             echo \"crash $n\" >&2\n[ $n -ge 4 ] && exit 0\nexit 1",
+        )],
+    );
+    let daemon = NotificationDaemon::start();
+
+    let output = qs
+        .command(&["shell", "--supervise"])
+        .env("PATH", path)
+        .env("RUNS", root.join("runs"))
+        .env("DBUS_SESSION_BUS_ADDRESS", daemon.address())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let state = supervisor_state(root);
+    assert_eq!(state["totalCrashes"], 3);
+    assert_eq!(state["restarts"], 3);
+    assert_eq!(state["lastStderr"], json!(["crash 3"]));
+    assert_eq!(state["pid"], serde_json::Value::Null);
+
+    // Later crashes update the first notification instead of stacking up.
+    let sent = daemon.sent();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(sent[0].app, "ferret-cli");
+    assert_eq!(sent[0].summary, "Shell crashed 2 times in a row");
+    assert_eq!(sent[0].body, "crash 2");
+    assert_eq!(sent[0].urgency, Some(2));
+    assert_eq!(sent[0].replaces, 0);
+    assert_eq!(sent[1].summary, "Shell crashed 3 times in a row");
+    assert_eq!(sent[1].body, "crash 3");
+    assert_eq!(sent[1].replaces, sent[0].id);
+    assert_eq!(sent[1].id, sent[0].id);
+}
+
+#[test]
+fn supervise_respects_notification_preferences() {
+    let daemon = NotificationDaemon::start();
+    let qs = MockShell::new();
+    let root = qs.root();
+    write_config(
+        root,
+        json!({
+            "shell": { "supervise": { "initialBackoff": 0.01, "notifyAfter": 1 } },
+            "notifications": { "categories": { "shell": { "enabled": false } } }
+        }),
+    );
+    let path = fake_tools(
+        root,
+        &[("qs", "[ -e \"$RAN\" ] && exit 0\ntouch \"$RAN\"\nexit 1")],
+    );
+
+    let output = qs
+        .command(&["shell", "--supervise"])
+        .env("PATH", path)
+        .env("RAN", root.join("ran"))
+        .env("DBUS_SESSION_BUS_ADDRESS", daemon.address())
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(supervisor_state(root)["totalCrashes"], 1);
+    assert!(daemon.sent().is_empty());
+}
+
+#[test]
+fn supervise_falls_back_to_default_for_huge_durations() {
+    let qs = MockShell::new();
+    let root = qs.root();
+    write_config(
+        root,
+        json!({ "shell": { "supervise": {
+            "initialBackoff": 0.01, "maxBackoff": 1e300, "stableAfter": 1e300
+        } } }),
+    );
+    let path = fake_tools(
+        root,
+        &[("qs", "[ -e \"$RAN\" ] && exit 0\ntouch \"$RAN\"\nexit 1")],
+    );
+
+    let output = qs
+        .command(&["shell", "--supervise"])
+        .env("PATH", path)
+        .env("RAN", root.join("ran"))
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("/shell/supervise/maxBackoff"));
+    assert!(stderr(&output).contains("/shell/supervise/stableAfter"));
+    assert_eq!(supervisor_state(root)["totalCrashes"], 1);
+}
+
+#[test]
+fn second_supervisor_refuses_to_start() {
+    let qs = MockShell::new();
+    let root = qs.root();
+    write_config(
+        root,
+        json!({ "shell": { "supervise": { "initialBackoff": 30 } } }),
+    );
+    let path = fake_tools(root, &[("qs", "exit 1")]);
+
+    let mut supervisor = qs
+        .command(&["shell", "--supervise"])
+        .env("PATH", &path)
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !root.join("state/ferret/shell/supervisor.json").exists()
+        || supervisor_state(root)["totalCrashes"] != 1
+    {
+        assert!(
+            Instant::now() < deadline,
+            "supervisor never recorded a crash"
+        );
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    let second = qs
+        .command(&["shell", "--supervise"])
+        .env("PATH", &path)
+        .output()
+        .unwrap();
+    supervisor.kill().unwrap();
+    supervisor.wait().unwrap();
+
+    assert!(!second.status.success());
+    assert!(stderr(&second).contains(&format!("already supervised (pid {})", supervisor.id())));
+    assert_eq!(supervisor_state(root)["totalCrashes"], 1);
+}
+
+#[test]
+fn kill_stops_supervisor_between_restarts() {
+    let qs = MockShell::new();
+    let root = qs.root();
+    write_config(
+        root,
+        json!({ "shell": { "supervise": { "initialBackoff": 30 } } }),
+    );
+    let path = fake_tools(root, &[("qs", "exit 1")]);
+
+    let mut supervisor = qs
+        .command(&["shell", "--supervise"])
+        .env("PATH", path)
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !root.join("state/ferret/shell/supervisor.json").exists()
+        || supervisor_state(root)["totalCrashes"] != 1
+    {
+        assert!(
+            Instant::now() < deadline,
+            "supervisor never recorded a crash"
+        );
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    // Nothing listens on this socket, as if the shell were down between restarts.
+    let kill = qs
+        .command(&["shell", "--kill"])
+        .env("FERRET_QS_SOCKET", root.join("missing.sock"))
+        .output()
+        .unwrap();
+    assert!(kill.status.success(), "{}", stderr(&kill));
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        if let Some(status) = supervisor.try_wait().unwrap() {
+            assert!(status.success());
+            break;
+        }
+        assert!(Instant::now() < deadline, "supervisor did not stop");
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn log_filters_and_formats_lines() {
+    let qs = MockShell::new();
+    let root = qs.root();
+    write_config(
+        root,
+        json!({ "shell": { "log": { "exclude": ["Binding loop"] } } }),
+    );
+    let path = fake_tools(
+        root,
+        &[(
+            "qs",
+            "echo \"$@\" > \"$ARGS\"\n\
+             echo '2024-05-01 14:00:00.000  INFO: Configuration Loaded'\n\
+             echo '2024-05-01 14:30:00.000  WARN scene: Binding loop detected'\n\
+             echo '2024-05-01 14:31:00.000 ERROR qml: TypeError'\n\
+             echo '    at Bar.qml:12'",
+        )],
+    );
+
+    let output = qs
+        .command(&["shell", "--log", "--json", "--since", "2024-05-01 14:15"])
+        .env("PATH", path)
+        .env("ARGS", root.join("args"))
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        fs::read_to_string(root.join("args")).unwrap().trim(),
+        "-c ferret log -t"
+    );
+    let lines: Vec<serde_json::Value> = stdout(&output)
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["level"], "error");
+    assert_eq!(lines[0]["category"], "qml");
+    assert_eq!(lines[0]["time"], "14:31:00.000");
+    assert_eq!(lines[1]["level"], "error");
+    assert_eq!(lines[1]["message"], "    at Bar.qml:12");
+}
+
+#[test]
+fn foreground_shell_filters_stdout_and_stderr() {
+    let qs = MockShell::new();
+    let root = qs.root();
+    let path = fake_tools(
+        root,
+        &[(
+            "qs",
+            "echo '  INFO: Configuration Loaded'\n\
+             echo '  WARN qml: Cannot open: file:///home/u/.cache/ferret/imagecache/a.png' >&2\n\
+             echo ' ERROR qml: TypeError' >&2",
+        )],
+    );
+
+    let output = qs.command(&["shell"]).env("PATH", path).output().unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "  INFO: Configuration Loaded\n");
+    assert_eq!(stderr(&output), " ERROR qml: TypeError\n");
+}
+
+#[test]
+fn shell_output_is_kept_in_log_files() {
+    let qs = MockShell::new();
+    let root = qs.root();
+    let path = fake_tools(
+        root,
+        &[(
+            "qs",
+            "echo '  INFO: Configuration Loaded'\n\
+             echo '  WARN qml: Cannot open: file:///home/u/.cache/ferret/imagecache/a.png' >&2\n\
+             echo ' ERROR qml: TypeError' >&2\nexit 1",
+        )],
+    );
+
+    let output = qs.command(&["shell"]).env("PATH", &path).output().unwrap();
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("the shell exited (exit status: 1)"));
+
+    let saved = fs::read_to_string(root.join("state/ferret/logs/shell.log")).unwrap();
+    assert_eq!(saved.lines().count(), 3);
+    assert!(saved.contains("imagecache"));
+
+    let output = qs
+        .command(&["shell", "--log", "--file"])
+        .env("PATH", &path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    let out = stdout(&output);
+    assert_eq!(out.lines().count(), 2, "{}", out);
+    assert!(out.contains("  INFO: Configuration Loaded"));
+    assert!(out.contains(" ERROR qml: TypeError"));
+}
+
+#[test]
+fn daemon_keeps_logging_in_background() {
+    let qs = MockShell::new();
+    let root = qs.root();
+    let path = fake_tools(root, &[("qs", "echo '  INFO: Configuration Loaded'")]);
+
+    let output = qs
+        .command(&["shell", "-d"])
+        .env("PATH", path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let log = root.join("state/ferret/logs/shell.log");
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !fs::read_to_string(&log).is_ok_and(|s| s.contains("Configuration Loaded")) {
+        assert!(Instant::now() < deadline, "daemon never wrote the log");
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn daemon_reports_shell_failing_to_start() {
+    let qs = MockShell::new();
+    let root = qs.root();
+    let path = fake_tools(root, &[("qs", "echo ' ERROR: broken config' >&2\nexit 1")]);
+
+    let output = qs
+        .command(&["shell", "-d"])
+        .env("PATH", path)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("the shell exited while starting (exit status: 1)"));
+    let log = fs::read_to_string(root.join("state/ferret/logs/shell.log")).unwrap();
+    assert!(log.contains("broken config"));
+}
+
+#[test]
+fn daemon_without_qs_fails() {
+    let qs = MockShell::new();
+    let empty = qs.root().join("empty");
+    fs::create_dir_all(&empty).unwrap();
+
+    let output = qs
+        .command(&["shell", "-d"])
+        .env("PATH", &empty)
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("qs is not installed"));
+}
+
+#[test]
+fn status_reports_healthy_shell() {
+    let qs = shell();
+    let root = qs.root();
+    let path = fake_tools(root, &[("qs", "echo 'quickshell 0.2.0'")]);
+
+    let output = qs
+        .command(&["shell", "--status", "--json"])
+        .env("PATH", &path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    let status: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(status["running"], true);
+    assert_eq!(status["version"], "quickshell 0.2.0");
+    assert_eq!(
+        status["ipc"],
+        json!({ "responding": true, "targets": 2, "error": null })
+    );
+    assert!(
+        status["config"]
+            .as_str()
+            .unwrap()
+            .ends_with("config/quickshell/ferret")
+    );
+
+    let output = qs
+        .command(&["shell", "--status"])
+        .env("PATH", &path)
+        .output()
+        .unwrap();
+    assert!(stdout(&output).contains("ipc: ok (2 targets)\n"));
+}
+
+#[test]
+fn status_exit_code_when_not_running() {
+    let qs = MockShell::new();
+    let root = qs.root();
+    let path = fake_tools(root, &[("qs", "exit 1")]);
+
+    let output = qs
+        .command(&["shell", "--status"])
+        .env("PATH", path)
+        .env("FERRET_QS_SOCKET", root.join("missing.sock"))
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stdout(&output).starts_with("shell: not running\n"));
+}
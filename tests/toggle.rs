@@ -0,0 +1,717 @@
+mod common;
+
+use common::notify::NotificationDaemon;
+use common::{MockHypr, client, monitor, stderr, workspace};
+use serde_json::json;
+use std::time::Duration;
+
+#[test]
+fn toggle_without_matching_clients_only_toggles_workspace() {
+    let hypr = MockHypr::new().with_toggles(json!({
+        "scratch": {
+            "notes": { "enable": true, "match": [{"class": "notes"}], "move": true }
+        }
+    }));
+
+    let output = hypr.ferret(&["toggle", "scratch"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        hypr.dispatches(),
+        vec!["dispatch togglespecialworkspace scratch"]
+    );
+}
+
+fn scratch_shell(launcher: serde_json::Value) -> MockHypr {
+    MockHypr::new().with_user_config(json!({
+        "launcher": launcher,
+        "toggles": {
+            "scratch": {
+                "shell": { "enable": true, "match": [{"class": "scratchsh"}], "command": ["sh", "-c", "exit 0"] }
+            }
+        }
+    }))
+}
+
+#[test]
+fn toggle_spawns_missing_command_without_toggling() {
+    let hypr = scratch_shell(json!({ "strategy": "exec" }));
+
+    let output = hypr.ferret(&["toggle", "scratch"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        hypr.dispatches(),
+        vec!["dispatch exec [workspace special:scratch] sh -c 'exit 0'"]
+    );
+}
+
+#[test]
+fn toggle_uses_custom_launcher_template() {
+    let hypr = scratch_shell(json!({ "strategy": "custom", "template": "runapp --scope {cmd}" }));
+
+    let output = hypr.ferret(&["toggle", "scratch"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        hypr.dispatches(),
+        vec!["dispatch exec [workspace special:scratch] runapp --scope sh -c 'exit 0'"]
+    );
+}
+
+#[test]
+fn toggle_wait_shows_workspace_once_window_appears() {
+    let hypr = scratch_shell(json!({ "strategy": "exec" }))
+        .spawning("exit 0", client("0x5", "scratchsh", "special:scratch"));
+
+    let output = hypr.ferret(&["toggle", "scratch", "--wait"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        hypr.dispatches(),
+        vec![
+            "dispatch exec [workspace special:scratch] sh -c 'exit 0'",
+            "dispatch togglespecialworkspace scratch",
+        ]
+    );
+}
+
+#[test]
+fn toggle_wait_times_out_without_showing_workspace() {
+    let hypr = scratch_shell(json!({ "strategy": "exec" }));
+
+    let output = hypr.ferret(&["toggle", "scratch", "--wait", "--wait-timeout", "0.3"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("shell did not open a window within 0.3s"));
+    assert_eq!(
+        hypr.dispatches(),
+        vec!["dispatch exec [workspace special:scratch] sh -c 'exit 0'"]
+    );
+}
+
+#[test]
+fn toggle_rejects_wait_timeout_out_of_range() {
+    let hypr = scratch_shell(json!({ "strategy": "exec" }));
+
+    for timeout in ["inf", "NaN", "1e20", "-1"] {
+        let output = hypr.ferret(&[
+            "toggle",
+            "scratch",
+            "--wait",
+            &format!("--wait-timeout={}", timeout),
+        ]);
+
+        assert!(!output.status.success(), "{} was accepted", timeout);
+        assert!(stderr(&output).contains("--wait-timeout"));
+    }
+    assert!(hypr.dispatches().is_empty());
+}
+
+#[test]
+fn toggle_sends_exec_with_window_rules_outside_batch() {
+    let hypr = MockHypr::new()
+        .with_client(client("0x1", "notes", "3"))
+        .with_user_config(json!({
+            "launcher": { "strategy": "exec" },
+            "toggles": {
+                "scratch": {
+                    "notes": { "enable": true, "match": [{"class": "notes"}], "move": true },
+                    "shell": {
+                        "enable": true, "match": [{"class": "scratchsh"}],
+                        "command": ["sh", "-c", "exit 0"], "float": true, "center": true
+                    }
+                }
+            }
+        }));
+
+    let output = hypr.ferret(&["toggle", "scratch"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let payloads = hypr.payloads();
+    assert!(payloads.contains(
+        &"dispatch exec [workspace special:scratch; float; center] sh -c 'exit 0'".to_string()
+    ));
+    assert!(payloads.contains(
+        &"[[BATCH]]dispatch movetoworkspacesilent special:scratch,address:0x1".to_string()
+    ));
+}
+
+#[test]
+fn exec_with_semicolon_in_command_is_sent_whole() {
+    let hypr = MockHypr::new()
+        .with_client(client("0x1", "notes", "3"))
+        .with_user_config(json!({
+            "launcher": { "strategy": "exec" },
+            "toggles": {
+                "scratch": {
+                    "notes": { "enable": true, "match": [{"class": "notes"}], "move": true },
+                    "shell": {
+                        "enable": true, "match": [{"class": "scratchsh"}],
+                        "command": ["sh", "-c", "sleep 1; exit 0"]
+                    }
+                }
+            }
+        }));
+
+    let output = hypr.ferret(&["toggle", "scratch"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let payloads = hypr.payloads();
+    assert!(payloads.contains(
+        &"dispatch exec [workspace special:scratch] sh -c 'sleep 1; exit 0'".to_string()
+    ));
+    assert!(payloads.contains(
+        &"[[BATCH]]dispatch movetoworkspacesilent special:scratch,address:0x1".to_string()
+    ));
+    assert!(hypr.dispatches().iter().all(|d| !d.ends_with("sleep 1")));
+}
+
+#[test]
+fn exec_without_reply_is_not_sent_again() {
+    let hypr = MockHypr::new()
+        .hanging_up_on("dispatch exec")
+        .with_user_config(json!({
+            "launcher": { "strategy": "exec" },
+            "toggles": {
+                "scratch": {
+                    "shell": {
+                        "enable": true, "match": [{"class": "scratchsh"}],
+                        "command": ["sh", "-c", "exit 0"], "float": true
+                    }
+                }
+            }
+        }));
+
+    let output = hypr.ferret(&["toggle", "scratch"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("no reply"));
+    let execs = hypr
+        .payloads()
+        .into_iter()
+        .filter(|p| p.starts_with("dispatch exec"))
+        .count();
+    assert_eq!(execs, 1);
+}
+
+#[test]
+fn toggle_warns_about_missing_command() {
+    let hypr = MockHypr::new().with_toggles(json!({
+        "scratch": {
+            "ghost": { "enable": true, "match": [{"class": "ghost"}], "command": ["ferret-no-such-binary"] }
+        }
+    }));
+
+    let output = hypr.ferret(&["toggle", "scratch"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("ferret-no-such-binary not found, not starting ghost"));
+    assert_eq!(
+        hypr.dispatches(),
+        vec!["dispatch togglespecialworkspace scratch"]
+    );
+}
+
+#[test]
+fn toggle_does_not_spawn_running_command() {
+    let hypr = MockHypr::new()
+        .with_client(client("0xabc", "scratchsh", "special:scratch"))
+        .with_toggles(json!({
+            "scratch": {
+                "shell": { "enable": true, "match": [{"class": "scratchsh"}], "command": ["sh"] }
+            }
+        }));
+
+    let output = hypr.ferret(&["toggle", "scratch"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        hypr.dispatches(),
+        vec!["dispatch togglespecialworkspace scratch"]
+    );
+}
+
+#[test]
+fn toggle_moves_matching_clients_into_special_workspace() {
+    let hypr = MockHypr::new()
+        .with_client(client("0x1", "notes", "3"))
+        .with_client(client("0x2", "notes", "special:scratch"))
+        .with_client(client("0x3", "other", "1"))
+        .with_toggles(json!({
+            "scratch": {
+                "notes": { "enable": true, "match": [{"class": "notes"}], "move": true }
+            }
+        }));
+
+    let output = hypr.ferret(&["toggle", "scratch"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        hypr.dispatches(),
+        vec![
+            "dispatch movetoworkspacesilent special:scratch,address:0x1",
+            "dispatch togglespecialworkspace scratch",
+        ]
+    );
+}
+
+#[test]
+fn toggle_places_moved_windows_on_focused_monitor() {
+    let hypr = MockHypr::new()
+        .with_client(client("0x1", "btop", "2"))
+        .with_toggles(json!({
+            "sysmon": {
+                "btop": {
+                    "enable": true, "match": [{"class": "btop"}], "move": true,
+                    "float": true, "size": ["50%", "50%"], "center": true
+                }
+            }
+        }));
+
+    let output = hypr.ferret(&["toggle", "sysmon"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        hypr.dispatches(),
+        vec![
+            "dispatch movetoworkspacesilent special:sysmon,address:0x1",
+            "dispatch setfloating address:0x1",
+            "dispatch resizewindowpixel exact 960 540,address:0x1",
+            "dispatch movewindowpixel exact 480 270,address:0x1",
+            "dispatch togglespecialworkspace sysmon",
+        ]
+    );
+}
+
+#[test]
+fn toggle_sends_all_actions_in_one_batch() {
+    let hypr = MockHypr::new()
+        .with_client(client("0x1", "notes", "3"))
+        .with_client(client("0x2", "notes", "4"))
+        .with_toggles(json!({
+            "scratch": {
+                "notes": { "enable": true, "match": [{"class": "notes"}], "move": true }
+            }
+        }));
+
+    let output = hypr.ferret(&["toggle", "scratch"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        hypr.payloads(),
+        vec![
+            "j/clients",
+            "[[BATCH]]dispatch movetoworkspacesilent special:scratch,address:0x1;\
+             dispatch movetoworkspacesilent special:scratch,address:0x2;\
+             dispatch togglespecialworkspace scratch",
+        ]
+    );
+}
+
+#[test]
+fn toggle_ignores_disabled_clients() {
+    let hypr = MockHypr::new()
+        .with_client(client("0x1", "notes", "3"))
+        .with_toggles(json!({
+            "scratch": {
+                "notes": { "enable": false, "match": [{"class": "notes"}], "move": true }
+            }
+        }));
+
+    let output = hypr.ferret(&["toggle", "scratch"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        hypr.dispatches(),
+        vec!["dispatch togglespecialworkspace scratch"]
+    );
+}
+
+#[test]
+fn specialws_toggles_focused_monitor_special_workspace() {
+    let hypr = MockHypr::new().with_monitors(vec![
+        monitor(0, "DP-1", false, "special:sysmon"),
+        monitor(1, "DP-2", true, "special:music"),
+    ]);
+
+    let output = hypr.ferret(&["toggle", "specialws"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        hypr.dispatches(),
+        vec!["dispatch togglespecialworkspace music"]
+    );
+}
+
+#[test]
+fn specialws_without_visible_special_workspace_uses_default() {
+    let hypr = MockHypr::new();
+
+    let output = hypr.ferret(&["toggle", "specialws"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        hypr.dispatches(),
+        vec!["dispatch togglespecialworkspace special"]
+    );
+}
+
+#[test]
+fn specialws_reopens_last_special_workspace_of_monitor() {
+    let hypr = MockHypr::new().with_monitors(vec![
+        monitor(0, "DP-1", true, "special:music"),
+        monitor(1, "DP-2", false, "special:sysmon"),
+    ]);
+
+    let hide = hypr.ferret(&["toggle", "specialws"]);
+    assert!(hide.status.success(), "{}", stderr(&hide));
+
+    hypr.set_monitors(vec![
+        monitor(0, "DP-1", true, ""),
+        monitor(1, "DP-2", false, "special:sysmon"),
+    ]);
+    let reopen = hypr.ferret(&["toggle", "specialws"]);
+    assert!(reopen.status.success(), "{}", stderr(&reopen));
+
+    assert_eq!(
+        hypr.dispatches(),
+        vec![
+            "dispatch togglespecialworkspace music",
+            "dispatch togglespecialworkspace music",
+        ]
+    );
+}
+
+fn focused_showing(special: &str) -> Vec<serde_json::Value> {
+    vec![
+        monitor(0, "DP-1", true, special),
+        monitor(1, "DP-2", false, "special:sysmon"),
+    ]
+}
+
+#[test]
+fn specialws_next_and_prev_step_through_open_workspaces() {
+    let hypr = MockHypr::new()
+        .with_monitors(focused_showing("special:music"))
+        .with_workspaces(vec![
+            workspace(1, "1", "DP-1"),
+            workspace(-95, "special:todo", "DP-1"),
+            workspace(-96, "special:chat", "DP-1"),
+            workspace(-97, "special:notes", "DP-1"),
+            workspace(-98, "special:music", "DP-1"),
+            workspace(-99, "special:sysmon", "DP-2"),
+        ]);
+
+    // Open on DP-1, sorted: chat, music, notes, todo. sysmon is shown on DP-2.
+    let steps = [
+        ("--next", "notes"),
+        ("--next", "todo"),
+        ("--next", "chat"),
+        ("--prev", "todo"),
+        ("--prev", "notes"),
+    ];
+    for (flag, shown) in steps {
+        let output = hypr.ferret(&["toggle", "specialws", flag]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        hypr.set_monitors(focused_showing(&format!("special:{}", shown)));
+    }
+
+    assert_eq!(
+        hypr.dispatches(),
+        vec![
+            "dispatch togglespecialworkspace notes",
+            "dispatch togglespecialworkspace todo",
+            "dispatch togglespecialworkspace chat",
+            "dispatch togglespecialworkspace todo",
+            "dispatch togglespecialworkspace notes",
+        ]
+    );
+}
+
+#[test]
+fn specialws_prev_without_visible_workspace_wraps_to_last() {
+    let hypr = MockHypr::new()
+        .with_monitors(focused_showing(""))
+        .with_workspaces(vec![
+            workspace(-97, "special:chat", "DP-1"),
+            workspace(-98, "special:music", "DP-1"),
+            workspace(-99, "special:sysmon", "DP-2"),
+        ]);
+
+    let output = hypr.ferret(&["toggle", "specialws", "--prev"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    assert_eq!(
+        hypr.dispatches(),
+        vec!["dispatch togglespecialworkspace music"]
+    );
+}
+
+#[test]
+fn next_requires_specialws() {
+    let hypr = MockHypr::new();
+
+    let output = hypr.ferret(&["toggle", "music", "--next"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("only apply to specialws"));
+}
+
+fn communication() -> serde_json::Value {
+    json!({
+        "communication": {
+            "discord": { "enable": true, "match": [{"class": "discord"}], "move": true },
+            "whatsapp": { "enable": true, "match": [{"class": "whatsapp"}], "move": true }
+        }
+    })
+}
+
+#[test]
+fn focus_brings_named_client_forward() {
+    let hypr = MockHypr::new()
+        .with_client(client("0x1", "discord", "special:communication"))
+        .with_client(client("0x2", "whatsapp", "4"))
+        .with_toggles(communication());
+
+    let output = hypr.ferret(&["toggle", "communication", "--focus", "whatsapp"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        hypr.dispatches(),
+        vec![
+            "dispatch movetoworkspacesilent special:communication,address:0x2",
+            "dispatch focuswindow address:0x2",
+        ]
+    );
+}
+
+#[test]
+fn cycle_focuses_window_after_active_one() {
+    let mut whatsapp = client("0x2", "whatsapp", "special:communication");
+    whatsapp["focusHistoryID"] = json!(1);
+    let hypr = MockHypr::new()
+        .with_client(client("0x1", "discord", "special:communication"))
+        .with_client(whatsapp)
+        .with_toggles(communication());
+
+    let output = hypr.ferret(&["toggle", "communication", "--cycle"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(hypr.dispatches(), vec!["dispatch focuswindow address:0x2"]);
+}
+
+#[test]
+fn focus_without_window_toggles_as_usual() {
+    let hypr = MockHypr::new().with_toggles(communication());
+
+    let output = hypr.ferret(&["toggle", "communication", "--focus", "discord"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        hypr.dispatches(),
+        vec!["dispatch togglespecialworkspace communication"]
+    );
+}
+
+#[test]
+fn focus_rejects_unknown_client() {
+    let hypr = MockHypr::new().with_toggles(communication());
+
+    let output = hypr.ferret(&["toggle", "communication", "--focus", "slack"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("no client slack in toggle group communication"));
+}
+
+fn prestart_config() -> MockHypr {
+    MockHypr::new()
+        .spawning("special:music silent] sh -c true", client("0x1", "player", "special:music"))
+        .spawning("special:scratch silent] sh", client("0x2", "scratchsh", "special:scratch"))
+        .with_user_config(json!({
+        "launcher": { "strategy": "exec" },
+        "toggles": {
+            "inheritDefaults": false,
+            "music": {
+                "player": { "enable": true, "match": [{"class": "player"}], "command": ["sh", "-c", "true"] }
+            },
+            "scratch": {
+                "shell": { "enable": true, "match": [{"class": "scratchsh"}], "command": ["sh"] },
+                "off": { "enable": false, "match": [{"class": "off"}], "command": ["sh"] }
+            }
+        }
+    }))
+}
+
+#[test]
+fn prestart_launches_every_group_silently() {
+    let hypr = prestart_config();
+
+    let output = hypr.ferret(&["toggle", "--prestart"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+    assert_eq!(
+        hypr.dispatches(),
+        vec![
+            "dispatch exec [workspace special:music silent] sh -c true",
+            "dispatch exec [workspace special:scratch silent] sh",
+        ]
+    );
+}
+
+#[test]
+fn prestart_is_idempotent() {
+    let hypr = prestart_config()
+        .with_client(client("0x1", "player", "special:music"))
+        .with_client(client("0x2", "scratchsh", "special:scratch"));
+
+    let output = hypr.ferret(&["toggle", "--prestart"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(hypr.dispatches().is_empty());
+}
+
+#[test]
+fn concurrent_prestarts_start_apps_once() {
+    let hypr = prestart_config().with_spawn_delay(Duration::from_millis(300));
+
+    let runs: Vec<_> = (0..2)
+        .map(|_| hypr.command(&["toggle", "--prestart"]).spawn().unwrap())
+        .collect();
+    for run in runs {
+        let output = run.wait_with_output().unwrap();
+        assert!(output.status.success(), "{}", stderr(&output));
+    }
+
+    assert_eq!(
+        hypr.dispatches(),
+        vec![
+            "dispatch exec [workspace special:music silent] sh -c true",
+            "dispatch exec [workspace special:scratch silent] sh",
+        ]
+    );
+}
+
+#[test]
+fn prestart_shows_progress_until_windows_appear() {
+    let daemon = NotificationDaemon::start();
+    let hypr = prestart_config().with_spawn_delay(Duration::from_millis(300));
+
+    let output = hypr
+        .command(&["toggle", "--prestart"])
+        .env("DBUS_SESSION_BUS_ADDRESS", daemon.address())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let sent = daemon.sent();
+    let (first, last) = (&sent[0], sent.last().unwrap());
+    assert_eq!(first.summary, "Starting apps");
+    assert_eq!(first.body, "0 of 2 open");
+    assert_eq!(first.value, Some(0));
+    assert_eq!(first.urgency, Some(0));
+    assert_eq!(last.body, "2 of 2 open");
+    assert_eq!(last.value, Some(100));
+    assert!(sent[1..].iter().all(|s| s.replaces == first.id));
+    assert_eq!(daemon.closed(), vec![first.id]);
+}
+
+#[test]
+fn prestart_warns_about_windows_that_never_appear() {
+    let hypr = MockHypr::new().with_user_config(json!({
+        "launcher": { "strategy": "exec" },
+        "toggles": {
+            "inheritDefaults": false,
+            "scratch": {
+                "shell": { "enable": true, "match": [{"class": "scratchsh"}], "command": ["sh"] }
+            }
+        }
+    }));
+
+    let output = hypr.ferret(&["toggle", "--prestart", "--wait-timeout", "0.2"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("shell did not open a window within 0.2s"));
+}
+
+#[test]
+fn prestart_single_group() {
+    let hypr = prestart_config();
+
+    let output = hypr.ferret(&["toggle", "--prestart", "scratch"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        hypr.dispatches(),
+        vec!["dispatch exec [workspace special:scratch silent] sh"]
+    );
+}
+
+#[test]
+fn toggle_reports_rejected_dispatch() {
+    let hypr = MockHypr::new().rejecting(
+        "dispatch togglespecialworkspace",
+        "Invalid dispatcher, requested \"togglespecialworkspace\" does not exist",
+    );
+
+    let output = hypr.ferret(&["toggle", "scratch"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("does not exist"));
+}
+
+#[test]
+fn list_shows_origin_of_each_entry() {
+    let hypr = MockHypr::new().with_toggles(json!({
+        "music": { "spotify": { "enable": false } },
+        "scratch": { "notes": { "enable": true, "match": [{"class": "notes"}] } }
+    }));
+
+    let output = hypr.ferret(&["toggle", "--list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout.contains("  discord (default)"));
+    assert!(stdout.contains("  spotify (user, overrides default)"));
+    assert!(stdout.contains("  notes (user)"));
+    assert!(hypr.requests().is_empty());
+}
+
+#[test]
+fn null_removes_default_groups_and_clients() {
+    let hypr = MockHypr::new().with_toggles(json!({
+        "music": null,
+        "communication": { "whatsapp": null }
+    }));
+
+    let output = hypr.ferret(&["toggle", "--list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!stdout.contains("music"));
+    assert!(!stdout.contains("whatsapp"));
+    assert!(stdout.contains("discord"));
+}
+
+#[test]
+fn inherit_defaults_false_drops_builtin_groups() {
+    let hypr = MockHypr::new()
+        .with_client(client("0x1", "discord", "2"))
+        .with_toggles(json!({
+            "inheritDefaults": false,
+            "scratch": { "notes": { "enable": true, "match": [{"class": "notes"}] } }
+        }));
+
+    let list = hypr.ferret(&["toggle", "--list"]);
+    let stdout = String::from_utf8_lossy(&list.stdout);
+    assert!(stdout.starts_with("scratch\n"));
+    assert!(!stdout.contains("communication"));
+
+    let output = hypr.ferret(&["toggle", "communication"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        hypr.dispatches(),
+        vec!["dispatch togglespecialworkspace communication"]
+    );
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::notify::NotificationDaemon;
+use common::{MockHypr, stderr, stdout};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn write_image(root: &Path, name: &str) -> PathBuf {
+    let file = root.join(name);
+    fs::create_dir_all(file.parent().unwrap()).unwrap();
+    image::RgbImage::from_pixel(8, 8, image::Rgb([40, 90, 160]))
+        .save(&file)
+        .unwrap();
+    file
+}
+
+#[test]
+fn setting_wallpaper_notifies_with_thumbnail() {
+    let daemon = NotificationDaemon::start();
+    let hypr = MockHypr::new();
+    let root = hypr.root();
+    let wall = write_image(root, "wall.png");
+
+    let output = hypr
+        .command(&["wallpaper", "-f", wall.to_str().unwrap()])
+        .env("DBUS_SESSION_BUS_ADDRESS", daemon.address())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let sent = daemon.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].summary, "Wallpaper changed");
+    assert_eq!(sent[0].body, wall.display().to_string());
+    assert_eq!(sent[0].urgency, Some(0));
+    assert!(sent[0].icon.starts_with("file://"));
+}
+
+#[test]
+fn unreadable_wallpaper_reports_scheme_error() {
+    let daemon = NotificationDaemon::start();
+    let hypr = MockHypr::new();
+    let root = hypr.root();
+    let scheme_path = root.join("state/ferret/scheme.json");
+    fs::create_dir_all(scheme_path.parent().unwrap()).unwrap();
+    fs::write(&scheme_path, r#"{"name": "dynamic", "mode": "dark"}"#).unwrap();
+    let wall = root.join("broken.png");
+    fs::write(&wall, "not a png").unwrap();
+
+    let output = hypr
+        .command(&["wallpaper", "-f", wall.to_str().unwrap()])
+        .env("DBUS_SESSION_BUS_ADDRESS", daemon.address())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let sent = daemon.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].summary, "Unable to set dynamic scheme");
+    assert!(sent[0].body.starts_with(&wall.display().to_string()));
+    assert_eq!(sent[0].urgency, Some(2));
+    assert_eq!(
+        fs::read_to_string(&scheme_path).unwrap(),
+        r#"{"name": "dynamic", "mode": "dark"}"#
+    );
+}
+
+#[test]
+fn random_wallpaper_goes_into_history() {
+    let hypr = MockHypr::new();
+    let root = hypr.root();
+    let first = write_image(root, "Pictures/Wallpapers/first.png");
+    let second = write_image(root, "Pictures/Wallpapers/nested/second.png");
+
+    let output = hypr.ferret(&["wallpaper", "-f", first.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    // The current wallpaper is never picked, so this has to be the other one.
+    let output = hypr.ferret(&["wallpaper", "--random"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let current = |hypr: &MockHypr| {
+        let output = hypr.ferret(&["wallpaper"]);
+        stdout(&output).trim().to_string()
+    };
+    assert_eq!(current(&hypr), second.display().to_string());
+
+    let output = hypr.ferret(&["wallpaper", "--previous"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(current(&hypr), first.display().to_string());
+}
+
+#[test]
+fn failed_restore_leaves_wallpaper_and_history_alone() {
+    let hypr = MockHypr::new();
+    let root = hypr.root();
+    let first = write_image(root, "first.png");
+    let second = write_image(root, "second.png");
+    for wall in [&first, &second] {
+        let output = hypr.ferret(&["wallpaper", "-f", wall.to_str().unwrap()]);
+        assert!(output.status.success(), "{}", stderr(&output));
+    }
+    let state = root.join("state/ferret/wallpaper");
+    let snapshot = || {
+        ["path.txt", "history.json", "thumbnail.jpg"]
+            .map(|name| fs::read(state.join(name)).unwrap())
+    };
+    let before = snapshot();
+
+    // The image is still there but its thumbnail can't be made.
+    fs::write(&first, "not a png any more").unwrap();
+    let output = hypr.ferret(&["wallpaper", "--previous"]);
+    assert!(!output.status.success());
+
+    assert_eq!(snapshot(), before);
+    assert_eq!(fs::read_link(state.join("current")).unwrap(), second);
+    let leftovers: Vec<_> = fs::read_dir(&state)
+        .unwrap()
+        .flatten()
+        .map(|entry| entry.file_name())
+        .filter(|name| {
+            !["path.txt", "history.json", "thumbnail.jpg", "current"]
+                .contains(&name.to_str().unwrap())
+        })
+        .collect();
+    assert!(leftovers.is_empty(), "{:?}", leftovers);
+}